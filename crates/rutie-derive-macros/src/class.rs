@@ -18,6 +18,7 @@ pub fn entry(
     }
 
     let mut ruby_module = None;
+    let mut mark = false;
 
     for argument in arguments.iter() {
         match argument {
@@ -29,6 +30,10 @@ pub fn entry(
                 ruby_module = Some(value.value());
             }
 
+            NestedMeta::Meta(Meta::Path(name)) if name.is_ident("mark") => {
+                mark = true;
+            }
+
             argument => panic!(
                 "Unexpected argument `{:?}` from the `rubyclass` procedural macro",
                 argument
@@ -42,6 +47,7 @@ pub fn entry(
             struct_data,
             &derive_input.generics,
             ruby_module.expect("The `module` argument of the `rubyclass` procedural macro is missing, e.g. `#[rubyclass(module = \"foo\")]`"),
+            mark,
         ),
 
         Data::Enum(_) => panic!("enums are not yet supported"),
@@ -59,6 +65,7 @@ fn derive_for_struct(
     _data: &DataStruct,
     generics: &Generics,
     ruby_module: String,
+    mark: bool,
 ) -> proc_macro2::TokenStream {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
@@ -78,6 +85,27 @@ fn derive_for_struct(
     let ruby_module = ruby_module_parts.next().unwrap();
     let ruby_nested_modules = ruby_module_parts.collect::<Vec<_>>();
 
+    // Mark the Ruby objects held by `XXX` with `rutie_derive::GcMark`
+    // if requested.
+    let wrappable_struct = if mark {
+        quote! {
+            wrappable_struct!(
+                #struct_name,
+                #wrapper_struct_name,
+                #wrapper_const_name,
+                mark(data) {
+                    use rutie_derive::GcMark;
+
+                    data.gc_mark();
+                }
+            );
+        }
+    } else {
+        quote! {
+            wrappable_struct!(#struct_name, #wrapper_struct_name, #wrapper_const_name);
+        }
+    };
+
     quote! {
         // Create the `XXXWrapper` wrapper class.
         //
@@ -86,7 +114,7 @@ fn derive_for_struct(
             use super::*; // to get `lazy_static`, imported by the user.
             use rutie::{wrappable_struct};
 
-            wrappable_struct!(#struct_name, #wrapper_struct_name, #wrapper_const_name);
+            #wrappable_struct
         }
 
         pub use #ruby_sub_module_hack::*;
@@ -0,0 +1,36 @@
+/// Marks the Ruby objects held by a Rust type, so that the Ruby
+/// garbage collector doesn't free them while they are still in use.
+///
+/// It must be implemented by the types declared with
+/// `#[rubyclass(module = "…", mark)]`.
+pub trait GcMark {
+    fn gc_mark(&self);
+}
+
+impl GcMark for rutie::AnyObject {
+    fn gc_mark(&self) {
+        rutie::GC::mark(self);
+    }
+}
+
+impl<T> GcMark for Option<T>
+where
+    T: GcMark,
+{
+    fn gc_mark(&self) {
+        if let Some(value) = self {
+            value.gc_mark();
+        }
+    }
+}
+
+impl<T> GcMark for Vec<T>
+where
+    T: GcMark,
+{
+    fn gc_mark(&self) {
+        for value in self {
+            value.gc_mark();
+        }
+    }
+}
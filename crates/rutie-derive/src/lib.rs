@@ -1,4 +1,6 @@
+mod gc;
 mod upcast;
 
+pub use gc::*;
 pub use rutie_derive_macros::{rubyclass, rubyfunction, rubymethods};
 pub use upcast::*;
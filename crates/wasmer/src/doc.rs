@@ -50,6 +50,17 @@ pub mod Ruby {
     /// ```
     pub struct String;
 
+    /// A native Ruby symbol.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// :hello
+    /// # "#); }
+    /// ```
+    pub struct Symbol;
+
     /// A native Ruby array.
     ///
     /// # Example
@@ -464,6 +475,29 @@ pub mod Wasmer {
         }
     }
 
+    /// Describes a function defined by a [`Module`], as returned by
+    /// [`Module::functions`].
+    pub struct FunctionInfo;
+
+    impl FunctionInfo {
+        /// Returns the index of the function in the function index
+        /// space of the module (imported functions come first).
+        pub fn index(&self) -> Integer {
+            x!()
+        }
+
+        /// Returns the name of the function, as found in the `name`
+        /// custom section, or `nil` if absent.
+        pub fn name(&self) -> Option<String> {
+            x!()
+        }
+
+        /// Returns the [`FunctionType`] of the function.
+        pub fn r#type(&self) -> FunctionType {
+            x!()
+        }
+    }
+
     /// The store represents all global state that can be manipulated
     /// by WebAssembly programs. It consists of the runtime
     /// representation of all instances of functions, tables,
@@ -473,8 +507,10 @@ pub mod Wasmer {
     /// The `Store` holds the engine (that is —amongst many things— used
     /// to compile the WebAssembly bytes into a valid module
     /// artifact), in addition to the Tunables (that are used to
-    /// create the memories, tables and globals). For the moment, it's
-    /// not possible to tweak the engines and the compilers.
+    /// create the memories, tables and globals). The engine can be
+    /// configured with [`Features`] to select the enabled
+    /// WebAssembly proposals. For the moment, it's not possible to
+    /// tweak the engines and the compilers.
     ///
     /// Specification: <https://webassembly.github.io/spec/core/exec/runtime.html#store>
     ///
@@ -487,11 +523,175 @@ pub mod Wasmer {
     /// store = Wasmer::Store.new
     /// # "#); }
     /// ```
+    ///
+    /// Use the store with a specific set of features:
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// store = Wasmer::Store.new features: Wasmer::Features.new(simd: false)
+    ///
+    /// assert { store.features.simd? == false }
+    /// # "#); }
+    /// ```
     pub struct Store;
 
     impl Store {
         /// Creates a new `Store`.
-        pub fn new() -> Self {
+        ///
+        /// The optional `features` keyword argument configures the
+        /// WebAssembly proposals enabled in the engine, and the
+        /// optional `target` keyword argument configures the
+        /// [`Target`] the engine compiles for (the host by default).
+        pub fn new(features: Option<Features>, target: Option<Target>) -> Self {
+            x!()
+        }
+
+        /// Returns the [`Features`] enabled in the engine.
+        pub fn features(&self) -> Features {
+            x!()
+        }
+
+        /// Returns the [`Target`] the engine compiles for.
+        pub fn target(&self) -> Target {
+            x!()
+        }
+    }
+
+    /// The target a [`Store`] compiles the WebAssembly modules
+    /// for. It is made of a target triple and a set of CPU features.
+    ///
+    /// A store with a target different from the host can compile
+    /// and [serialize](Module::serialize) modules for another
+    /// architecture, but it cannot instantiate them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// target = Wasmer::Target.new "aarch64-unknown-linux-gnu", []
+    /// store = Wasmer::Store.new target: target
+    ///
+    /// assert { store.target.triple == "aarch64-unknown-linux-gnu" }
+    ///
+    /// ## Serialize a module for the `aarch64` architecture.
+    /// serialized_module = Wasmer::Module.new(store, "(module)").serialize
+    /// # "#); }
+    /// ```
+    pub struct Target;
+
+    impl Target {
+        /// Creates a new `Target` from a target triple, and a list
+        /// of CPU features, like `"sse2"` or `"avx"`.
+        pub fn new(triple: String, cpu_features: Array<String>) -> Self {
+            x!()
+        }
+
+        /// Returns the target of the host.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// assert { Wasmer::Store.new.target.triple == Wasmer::Target.host.triple }
+        /// # "#); }
+        /// ```
+        pub fn host() -> Self {
+            x!()
+        }
+
+        /// Returns the target triple.
+        pub fn triple(&self) -> String {
+            x!()
+        }
+
+        /// Returns the CPU features.
+        pub fn cpu_features(&self) -> Array<String> {
+            x!()
+        }
+    }
+
+    /// Controls which WebAssembly proposals are enabled in the engine
+    /// of a [`Store`].
+    ///
+    /// Modules using a disabled proposal are rejected by
+    /// [`Module::validate`] and [`Module::new`]. Each proposal is
+    /// set with a keyword argument, the other ones keep their
+    /// default value. By default, SIMD, reference types, bulk memory
+    /// and multi-value are enabled; threads, 64-bit memory and module
+    /// linking are disabled.
+    ///
+    /// Note that the reference types proposal depends on the bulk
+    /// memory proposal: enabling the former enables the latter, and
+    /// disabling the latter disables the former.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// features = Wasmer::Features.new simd: false, threads: true
+    /// store = Wasmer::Store.new features: features
+    ///
+    /// ## This module uses a SIMD instruction.
+    /// wasm_bytes = Wasmer::wat2wasm(
+    ///   (<<~WAST)
+    ///   (module
+    ///     (func (result v128)
+    ///       v128.const i32x4 1 2 3 4))
+    ///   WAST
+    /// )
+    ///
+    /// assert { Wasmer::Module.validate(store, wasm_bytes) == false }
+    /// assert { Wasmer::Module.validate(Wasmer::Store.new, wasm_bytes) == true }
+    /// # "#); }
+    /// ```
+    pub struct Features;
+
+    impl Features {
+        /// Creates a new `Features`.
+        pub fn new(
+            simd: Option<Boolean>,
+            threads: Option<Boolean>,
+            reference_types: Option<Boolean>,
+            bulk_memory: Option<Boolean>,
+            multi_value: Option<Boolean>,
+            memory64: Option<Boolean>,
+            module_linking: Option<Boolean>,
+        ) -> Self {
+            x!()
+        }
+
+        /// Checks whether the SIMD proposal is enabled.
+        pub fn simd(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the threads proposal is enabled.
+        pub fn threads(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the reference types proposal is enabled.
+        pub fn reference_types(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the bulk memory proposal is enabled.
+        pub fn bulk_memory(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the multi-value proposal is enabled.
+        pub fn multi_value(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the 64-bit memory proposal is enabled.
+        pub fn memory64(&self) -> Boolean {
+            x!()
+        }
+
+        /// Checks whether the module linking proposal is enabled.
+        pub fn module_linking(&self) -> Boolean {
             x!()
         }
     }
@@ -548,7 +748,11 @@ pub mod Wasmer {
         }
 
         /// Creates a new [`Module`].
-        pub fn new(store: Store, bytes: String) -> Self {
+        ///
+        /// The optional `verify` keyword argument is a [`Keyring`]:
+        /// the module must have been signed by one of its keys,
+        /// otherwise a `Wasmer::SignatureError` is raised.
+        pub fn new(store: Store, bytes: String, verify: Option<Keyring>) -> Self {
             x!()
         }
 
@@ -635,9 +839,99 @@ pub mod Wasmer {
             x!()
         }
 
+        /// Returns the names of all the custom sections of the
+        /// module, without duplicates.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// bytes = IO.read "custom_sections.wasm", mode: "rb"
+        /// module_ = Wasmer::Module.new Wasmer::Store.new, bytes
+        ///
+        /// assert { module_.custom_section_names.include? "easter_egg" }
+        /// # "#); }
+        /// ```
+        pub fn custom_section_names(&self) -> Array<String> {
+            x!()
+        }
+
+        /// Returns a list of [`FunctionInfo`] objects, which
+        /// represents all the functions defined by this module
+        /// (imported functions are excluded).
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "env" "log" (func $log (param i32)))
+        ///     (func $sum (param i32 i32) (result i32)
+        ///       local.get 0
+        ///       local.get 1
+        ///       i32.add))
+        ///   WAST
+        /// )
+        ///
+        /// functions = module_.functions
+        ///
+        /// assert { functions.length == 1 }
+        /// assert { functions[0].index == 1 }
+        /// assert { functions[0].name == "sum" }
+        /// assert { functions[0].type.params == [Wasmer::Type::I32, Wasmer::Type::I32] }
+        /// # "#); }
+        /// ```
+        pub fn functions(&self) -> Array<FunctionInfo> {
+            x!()
+        }
+
+        /// Returns the index of the start function of the module, or
+        /// `nil` if there is none.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new Wasmer::Store.new, "(module (func) (start 0))"
+        ///
+        /// assert { module_.start_function == 0 }
+        /// # "#); }
+        /// ```
+        pub fn start_function(&self) -> Option<Integer> {
+            x!()
+        }
+
+        /// Returns a list of [`MemoryType`] objects, which represents
+        /// the memories defined by this module (imported memories
+        /// are excluded).
+        pub fn memories(&self) -> Array<MemoryType> {
+            x!()
+        }
+
+        /// Returns a list of [`GlobalType`] objects, which represents
+        /// the globals defined by this module (imported globals are
+        /// excluded).
+        pub fn globals(&self) -> Array<GlobalType> {
+            x!()
+        }
+
+        /// Returns a list of [`TableType`] objects, which represents
+        /// the tables defined by this module (imported tables are
+        /// excluded).
+        pub fn tables(&self) -> Array<TableType> {
+            x!()
+        }
+
         /// Serializes a module into a binary representation that the
         /// engine can later process via [`Module::deserialize`].
         ///
+        /// The binary representation records the target triple and
+        /// the CPU features of the store the module has been compiled
+        /// with.
+        ///
         /// # Example
         ///
         /// ```rust
@@ -655,6 +949,13 @@ pub mod Wasmer {
         /// **Note**: the module has to be serialized before with the
         /// serialize method.
         ///
+        /// A `RuntimeError` is raised if the module has been
+        /// serialized for a target triple different from the one of
+        /// the given store, or for CPU features that the target of
+        /// the store doesn't have. Modules serialized by a previous
+        /// version, which doesn't record the target, are deserialized
+        /// without this check.
+        ///
         /// # Safety
         ///
         /// This function is inherently unsafe as the provided bytes:
@@ -697,6 +998,148 @@ pub mod Wasmer {
         }
     }
 
+    /// A set of trusted ed25519 public keys, used to verify the
+    /// signature of a module before compiling it, with
+    /// `Wasmer::Module.new(store, bytes, verify: keyring)`.
+    ///
+    /// The signature lives in the `signature` custom section
+    /// (`Wasmer::Keyring::SIGNATURE_SECTION`). It is computed over the
+    /// module bytes without this custom section, which can be
+    /// obtained with [`ModuleBytes::strip_custom_section`]. A module
+    /// can hold several signatures; one of them must be made by a key
+    /// of the keyring.
+    ///
+    /// If the signature is missing or invalid, a
+    /// `Wasmer::SignatureError` (a subclass of `RuntimeError`) is
+    /// raised.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// keyring = Wasmer::Keyring.new.add_key_file "signed.pub"
+    /// bytes = IO.read "signed.wasm", mode: "rb"
+    ///
+    /// module_ = Wasmer::Module.new Wasmer::Store.new, bytes, verify: keyring
+    /// # "#); }
+    /// ```
+    pub struct Keyring;
+
+    impl Keyring {
+        /// Creates an empty `Keyring`.
+        pub fn new() -> Self {
+            x!()
+        }
+
+        /// Adds a raw ed25519 public key (32 bytes). Returns `self`.
+        pub fn add_key(&mut self, key: String) -> Self {
+            x!()
+        }
+
+        /// Adds an ed25519 public key read from a local file. The
+        /// file contains the key either raw (32 bytes), or
+        /// hexadecimal-encoded. Returns `self`.
+        pub fn add_key_file(&mut self, path: String) -> Self {
+            x!()
+        }
+
+        /// Returns the number of keys.
+        pub fn length(&self) -> Integer {
+            x!()
+        }
+    }
+
+    /// The bytes of a WebAssembly module, that can be rewritten
+    /// before being compiled with [`Module::new`]. It allows to add,
+    /// replace and strip custom sections, e.g. to stamp a module with
+    /// some metadata, or to strip its debug information.
+    ///
+    /// All the other sections are kept as is, in the same order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// module_bytes = Wasmer::ModuleBytes.new "(module (func $foo))"
+    ///
+    /// bytes = module_bytes
+    ///   .strip_debug_sections
+    ///   .add_custom_section("author", "Wasmer")
+    ///   .to_s
+    ///
+    /// module_ = Wasmer::Module.new Wasmer::Store.new, bytes
+    ///
+    /// assert { module_.custom_section_names == ["author"] }
+    /// assert { module_.custom_sections("author") == ["Wasmer"] }
+    /// # "#); }
+    /// ```
+    pub struct ModuleBytes;
+
+    impl ModuleBytes {
+        /// Parses the bytes of a WebAssembly module. If the bytes
+        /// are not WebAssembly-like, they are assumed to be in the
+        /// WebAssembly text format.
+        pub fn new(bytes: String) -> Self {
+            x!()
+        }
+
+        /// Returns the names of all the custom sections, without
+        /// duplicates.
+        pub fn custom_section_names(&self) -> Array<String> {
+            x!()
+        }
+
+        /// Returns the data of all the custom sections with the given
+        /// `name`, similarly to [`Module::custom_sections`].
+        pub fn custom_sections(&self, name: String) -> Array<String> {
+            x!()
+        }
+
+        /// Appends a new custom section at the end of the
+        /// module. Returns `self`.
+        pub fn add_custom_section(&mut self, name: String, data: String) -> Self {
+            x!()
+        }
+
+        /// Replaces the custom sections with the given `name` by a
+        /// single one, at the position of the first one. If there is
+        /// no such custom section, it is appended. Returns `self`.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_bytes = Wasmer::ModuleBytes.new "(module)"
+        /// module_bytes.add_custom_section "version", "1"
+        /// module_bytes.add_custom_section "version", "2"
+        /// module_bytes.replace_custom_section "version", "3"
+        ///
+        /// assert { module_bytes.custom_sections("version") == ["3"] }
+        /// # "#); }
+        /// ```
+        pub fn replace_custom_section(&mut self, name: String, data: String) -> Self {
+            x!()
+        }
+
+        /// Removes all the custom sections with the given
+        /// `name`. Returns `self`.
+        pub fn strip_custom_section(&mut self, name: String) -> Self {
+            x!()
+        }
+
+        /// Removes the debug custom sections, i.e. the `name`
+        /// section and the DWARF sections (`.debug_*`). Returns
+        /// `self`.
+        pub fn strip_debug_sections(&mut self) -> Self {
+            x!()
+        }
+
+        /// Returns the bytes of the rewritten module.
+        pub fn to_s(&self) -> String {
+            x!()
+        }
+    }
+
     /// A WebAssembly instance is a stateful, executable instance of a
     /// WebAssembly [`Module`].
     ///
@@ -773,18 +1216,172 @@ pub mod Wasmer {
     /// assert { instance.exports.add_one.(1) == 2 }
     /// # "#); }
     /// ```
+    ///
+    /// The same example with the `imports` keyword argument. The
+    /// namespaces are given as nested `Hash`es, and the type of the
+    /// `math.sum` function is inferred from the imports of the
+    /// module, so that a bare `Proc` or `Method` is enough.
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// module_ = Wasmer::Module.new(
+    ///   Wasmer::Store.new,
+    ///   (<<~WAST)
+    ///   (module
+    ///     (import "math" "sum" (func $sum (param i32 i32) (result i32)))
+    ///     (func (export "add_one") (param i32) (result i32)
+    ///       local.get 0
+    ///       i32.const 1
+    ///       call $sum))
+    ///   WAST
+    /// )
+    ///
+    /// instance = Wasmer::Instance.new(
+    ///   module_,
+    ///   imports: { "math" => { "sum" => ->(x, y) { x + y } } }
+    /// )
+    ///
+    /// assert { instance.exports.add_one.(1) == 2 }
+    /// # "#); }
+    /// ```
     pub struct Instance;
 
     impl Instance {
         /// Creates a new `Instance`.
-        pub fn new(module: Module, import_object: ImportObject) -> Self {
-            x!()
-        }
-
+        ///
+        /// The imports are given either by an [`ImportObject`], or
+        /// by the `imports` keyword argument, which is a `Hash` of
+        /// namespaces, each namespace being a `Hash` of [`Function`],
+        /// [`Memory`], [`Global`] or [`Table`]. In the latter case,
+        /// a function can also be a `Symbol`, a `Proc` or a
+        /// `Method`: it is wrapped into a [`Function`] whose type is
+        /// the type of the matching import of the module. Passing
+        /// both is an `ArgumentError`.
+        ///
+        /// The start function of the module, if any, is called
+        /// during the instantiation, unless the `call_start` keyword
+        /// argument is `false`. In this case, it must be called later
+        /// with [`Instance::start`].
+        ///
+        /// With `initialize: true`, the `_initialize` function
+        /// exported by reactors, e.g. WASI reactors, is called after
+        /// the start function (or deferred with it), and it is
+        /// removed from the exports so that it isn't called twice.
+        ///
+        /// # Errors
+        ///
+        /// * `Wasmer::LinkError` is raised if an import is missing or
+        ///   has an incompatible type. It has the `module`, `name`,
+        ///   `expected` and `provided` (`nil` if missing)
+        ///   attributes, where the types are [`FunctionType`],
+        ///   [`MemoryType`], [`GlobalType`] or [`TableType`],
+        /// * `Wasmer::StartError` is raised if the start function
+        ///   traps,
+        /// * `Wasmer::InstantiationError`, the parent class of the two
+        ///   previous errors, is raised for any other error.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "env" "log" (func (param i32))))
+        ///   WAST
+        /// )
+        ///
+        /// begin
+        ///   Wasmer::Instance.new module_, nil
+        /// rescue Wasmer::LinkError => error
+        ///   assert { error.module == "env" }
+        ///   assert { error.name == "log" }
+        ///   assert { error.expected.params == [Wasmer::Type::I32] }
+        ///   assert { error.provided.nil? }
+        /// end
+        /// # "#); }
+        /// ```
+        pub fn new(
+            module: Module,
+            import_object: Option<ImportObject>,
+            imports: Option<Hash<String, Hash<String, Any>>>,
+            call_start: Option<Boolean>,
+            initialize: Option<Boolean>,
+        ) -> Self {
+            x!()
+        }
+
         /// Returns all the exported entities.
         pub fn exports(&self) -> Exports {
             x!()
         }
+
+        /// Defines a real singleton method on `object` for each
+        /// exported function, like [`Exports::to_module`] does, and
+        /// returns `object`.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (func (export "sum") (param i32 i32) (result i32)
+        ///       local.get 0
+        ///       local.get 1
+        ///       i32.add))
+        ///   WAST
+        /// )
+        /// calculator = Object.new
+        /// Wasmer::Instance.new(module_, nil).define_methods_on calculator
+        ///
+        /// assert { calculator.sum(1, 2) == 3 }
+        /// assert { calculator.singleton_methods == [:sum] }
+        /// # "#); }
+        /// ```
+        pub fn define_methods_on(&self, object: Any) -> Any {
+            x!()
+        }
+
+        /// Calls the start function of the module, then the
+        /// `_initialize` function of a reactor (with `initialize:
+        /// true`), if their calls have
+        /// been deferred with `call_start: false`, and if they have
+        /// not been called yet. A `Wasmer::StartError` is raised if
+        /// one of them traps.
+        ///
+        /// Note that the start function of a deserialized module
+        /// cannot be deferred.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (global $initialized (export "initialized") (mut i32) (i32.const 0))
+        ///     (func $start
+        ///       i32.const 1
+        ///       global.set $initialized)
+        ///     (start $start))
+        ///   WAST
+        /// )
+        ///
+        /// instance = Wasmer::Instance.new module_, nil, call_start: false
+        /// assert { instance.exports.initialized.value == 0 }
+        ///
+        /// instance.start
+        /// assert { instance.exports.initialized.value == 1 }
+        /// # "#); }
+        /// ```
+        pub fn start(&mut self) {
+            x!()
+        }
     }
 
     /// Represents all the exports of an instance. It is built by [`Instance::exports`].
@@ -833,6 +1430,167 @@ pub mod Wasmer {
         pub fn method_missing(name: String) -> Any {
             x!()
         }
+
+        /// Returns either a [`Function`], a [`Memory`], a [`Global`],
+        /// or a [`Table`] if the name for the export exists, `nil`
+        /// otherwise. The name can be a `String` or a `Symbol`, which
+        /// is handy for exports whose names aren't valid Ruby
+        /// identifiers.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (func (export "my-func"))
+        ///     (global (export "__heap_base") i32 (i32.const 7)))
+        ///   WAST
+        /// )
+        /// exports = Wasmer::Instance.new(module_, nil).exports
+        ///
+        /// assert { exports["my-func"].is_a?(Wasmer::Function) }
+        /// assert { exports[:__heap_base].value == 7 }
+        /// assert { exports["foo"].nil? }
+        /// # "#); }
+        /// ```
+        pub fn get(&self, name: String) -> Option<Any> {
+            x!()
+        }
+
+        /// Yields the name and the object of each export, in the
+        /// order of the module, and returns `self`. Without a block,
+        /// it returns an `Enumerator`. `Exports` doesn't include
+        /// `Enumerable`, so that its methods (e.g. `sum` or `count`)
+        /// don't hide the exports of the same name: use the
+        /// `Enumerator` instead.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (func (export "func"))
+        ///     (memory (export "mem") 1))
+        ///   WAST
+        /// )
+        /// exports = Wasmer::Instance.new(module_, nil).exports
+        ///
+        /// assert { exports.each.map { |name, _| name } == ["func", "mem"] }
+        /// assert { exports.each.next.first == "func" }
+        /// # "#); }
+        /// ```
+        pub fn each(&self) -> Self {
+            x!()
+        }
+
+        /// Returns the names of the exports.
+        pub fn keys(&self) -> Array<String> {
+            x!()
+        }
+
+        /// Returns a `Hash` of all the exports, indexed by name.
+        pub fn to_h(&self) -> Hash<String, Any> {
+            x!()
+        }
+
+        /// Returns a new anonymous Ruby `Module` with a real
+        /// singleton method per exported function, which is faster
+        /// than `method_missing` and visible to introspection.
+        ///
+        /// The arity of each method is the number of parameters of
+        /// the function. The export names that aren't valid Ruby
+        /// identifiers are sanitized: the invalid characters are
+        /// replaced by `_`, and a leading digit is prefixed by `_`.
+        /// The functions named like a method that a module already
+        /// has (e.g. `send` or `inspect`) are skipped. The `inspect`
+        /// method of the module lists the signatures of the
+        /// functions.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (func (export "sum") (param i32 i32) (result i32)
+        ///       local.get 0
+        ///       local.get 1
+        ///       i32.add)
+        ///     (func (export "do-nothing")))
+        ///   WAST
+        /// )
+        /// math = Wasmer::Instance.new(module_, nil).exports.to_module
+        ///
+        /// assert { math.sum(1, 2) == 3 }
+        /// assert { math.method(:sum).arity == 2 }
+        /// assert { math.respond_to?(:do_nothing) }
+        /// assert { math.inspect == "#<Wasmer exports: sum(i32, i32) -> i32, do_nothing() -> ()>" }
+        /// # "#); }
+        /// ```
+        pub fn to_module(&self) -> Any {
+            x!()
+        }
+
+        /// Returns the [`Function`] named `name`. A `NameError` is
+        /// raised if the export does not exist, and a `TypeError` is
+        /// raised if it is not a function.
+        ///
+        /// Without argument, it returns the export named `function`
+        /// (of any kind), like [`Exports::method_missing`].
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (func (export "func"))
+        ///     (memory (export "memory") 1))
+        ///   WAST
+        /// )
+        /// exports = Wasmer::Instance.new(module_, nil).exports
+        ///
+        /// assert { exports.function("func").is_a?(Wasmer::Function) }
+        /// assert { exports.memory.is_a?(Wasmer::Memory) }
+        ///
+        /// begin
+        ///   exports.function("memory")
+        /// rescue TypeError
+        ///   assert { true }
+        /// end
+        /// # "#); }
+        /// ```
+        pub fn function(&self, name: Option<String>) -> Function {
+            x!()
+        }
+
+        /// Returns the [`Memory`] named `name`. See
+        /// [`Exports::function`] to learn more.
+        pub fn memory(&self, name: Option<String>) -> Memory {
+            x!()
+        }
+
+        /// Returns the [`Global`] named `name`. See
+        /// [`Exports::function`] to learn more.
+        pub fn global(&self, name: Option<String>) -> Global {
+            x!()
+        }
+
+        /// Returns the [`Table`] named `name`. See
+        /// [`Exports::function`] to learn more.
+        pub fn table(&self, name: Option<String>) -> Table {
+            x!()
+        }
     }
 
     /// An `ImportObject` represents all of the import data used when
@@ -969,10 +1727,98 @@ pub mod Wasmer {
 
     impl ImportObject {
         /// Creates a new `ImportObject`.
+        ///
+        /// An optional block resolves the imports that aren't
+        /// registered when a module is instantiated. It receives the
+        /// namespace, the name and the type ([`FunctionType`],
+        /// [`MemoryType`], [`GlobalType`] or [`TableType`]) of the
+        /// import, and returns a [`Function`], a [`Memory`], a
+        /// [`Global`], a [`Table`], or `nil` for a link error.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// store = Wasmer::Store.new
+        /// module_ = Wasmer::Module.new(
+        ///   store,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "math" "double" (func $double (param i32) (result i32)))
+        ///     (import "math" "triple" (func $triple (param i32) (result i32)))
+        ///     (func (export "six_times") (param i32) (result i32)
+        ///       local.get 0
+        ///       call $double
+        ///       call $triple))
+        ///   WAST
+        /// )
+        ///
+        /// factors = { "double" => 2, "triple" => 3 }
+        ///
+        /// import_object = Wasmer::ImportObject.new do |namespace, name, type|
+        ///   factor = factors[name]
+        ///   Wasmer::Function.new(store, ->(x) { x * factor }, type) if namespace == "math" && factor
+        /// end
+        ///
+        /// instance = Wasmer::Instance.new module_, import_object
+        ///
+        /// assert { instance.exports.six_times.(7) == 42 }
+        /// # "#); }
+        /// ```
         pub fn new() -> Self {
             x!()
         }
 
+        /// Generates an `ImportObject` with stubs for all the imports
+        /// of a module: functions of the right [`FunctionType`], and
+        /// dummy [`Memory`], [`Global`] (holding zero) and [`Table`]
+        /// (holding null references) of the right type.
+        ///
+        /// The `mode` keyword argument says what the stub functions
+        /// do when they are called:
+        ///
+        /// * `:trap` (the default) traps,
+        /// * `:zero` returns zeros,
+        /// * `:log` returns zeros, and records the call (see
+        ///   [`ImportObject::calls`]).
+        ///
+        /// To stub only the missing imports, merge the real imports
+        /// into the stubs with [`ImportObject::merge`].
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "env" "log" (func $log (param i32) (result i32)))
+        ///     (func (export "run") (result i32)
+        ///       i32.const 42
+        ///       call $log))
+        ///   WAST
+        /// )
+        ///
+        /// import_object = Wasmer::ImportObject.stubs_for module_, mode: :log
+        /// instance = Wasmer::Instance.new module_, import_object
+        ///
+        /// assert { instance.exports.run.() == 0 }
+        /// assert { import_object.calls == [["env", "log", [42]]] }
+        /// # "#); }
+        /// ```
+        pub fn stubs_for(module: Module, mode: Option<Symbol>) -> Self {
+            x!()
+        }
+
+        /// Returns the calls to the stub functions generated by
+        /// [`ImportObject::stubs_for`] with the `:log` mode, as
+        /// `[namespace_name, name, arguments]` arrays, in call
+        /// order.
+        pub fn calls(&self) -> Array<Array<Any>> {
+            x!()
+        }
+
         /// Checks whether the import object contains a specific
         /// namespace.
         pub fn contains_namespace(&self, namespace_name: String) -> Boolean {
@@ -980,11 +1826,258 @@ pub mod Wasmer {
         }
 
         /// Registers a set of [`Function`], [`Memory`], [`Global`] or
-        /// [`Table`] to a particular namespace.
+        /// [`Table`] to a particular namespace. The set is either a
+        /// `Hash` indexed by name, or the [`Exports`] of another
+        /// instance, so that instances can be linked together.
         ///
         /// See the [`ImportObject`]'s documentation to see more
         /// examples.
-        pub fn register(&self, namespace_name: String, namespace: Hash<String, Any>) {
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// store = Wasmer::Store.new
+        /// math = Wasmer::Instance.new(
+        ///   Wasmer::Module.new(
+        ///     store,
+        ///     (<<~WAST)
+        ///     (module
+        ///       (func (export "sum") (param i32 i32) (result i32)
+        ///         local.get 0
+        ///         local.get 1
+        ///         i32.add))
+        ///     WAST
+        ///   ),
+        ///   nil
+        /// )
+        ///
+        /// import_object = Wasmer::ImportObject.new
+        /// import_object.register "math", math.exports
+        ///
+        /// module_ = Wasmer::Module.new(
+        ///   store,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "math" "sum" (func $sum (param i32 i32) (result i32)))
+        ///     (func (export "add_one") (param i32) (result i32)
+        ///       local.get 0
+        ///       i32.const 1
+        ///       call $sum))
+        ///   WAST
+        /// )
+        /// instance = Wasmer::Instance.new module_, import_object
+        ///
+        /// assert { instance.exports.add_one.(1) == 2 }
+        /// # "#); }
+        /// ```
+        pub fn register(&self, namespace_name: String, namespace: Any) {
+            x!()
+        }
+
+        /// Removes a namespace, and returns its externs as a `Hash`
+        /// indexed by name, or `nil` if it was not registered.
+        pub fn unregister(&mut self, namespace_name: String) -> Option<Hash<String, Any>> {
+            x!()
+        }
+
+        /// Returns the names of the registered namespaces.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// store = Wasmer::Store.new
+        /// global = Wasmer::Global.new store, Wasmer::Value.i32(7), false
+        ///
+        /// import_object = Wasmer::ImportObject.new
+        /// import_object.register "env", { :global => global }
+        ///
+        /// assert { import_object.namespaces == ["env"] }
+        /// assert { import_object["env"] == { "global" => global } }
+        /// assert { import_object.get("env", :global) == global }
+        /// assert { import_object.get("env", "missing").nil? }
+        /// assert { import_object.to_h == { "env" => { "global" => global } } }
+        ///
+        /// import_object.unregister "env"
+        /// assert { import_object.namespaces.empty? }
+        /// # "#); }
+        /// ```
+        pub fn namespaces(&self) -> Array<String> {
+            x!()
+        }
+
+        /// Returns the externs of a namespace as a `Hash` indexed by
+        /// name, or `nil` if the namespace is not registered. It is
+        /// called with `import_object[namespace_name]`.
+        pub fn get_namespace(&self, namespace_name: String) -> Option<Hash<String, Any>> {
+            x!()
+        }
+
+        /// Returns the extern `name` of the namespace
+        /// `namespace_name`, or `nil` if it is not registered.
+        pub fn get(&self, namespace_name: String, name: String) -> Option<Any> {
+            x!()
+        }
+
+        /// Returns a new `ImportObject` with the namespaces of both
+        /// import objects. When both have the same extern in the same
+        /// namespace, or both have a resolver block, the one of
+        /// `other` wins.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// store = Wasmer::Store.new
+        /// one = Wasmer::Global.new store, Wasmer::Value.i32(1), false
+        /// two = Wasmer::Global.new store, Wasmer::Value.i32(2), false
+        ///
+        /// libc = Wasmer::ImportObject.new
+        /// libc.register "env", { :one => one, :two => one }
+        ///
+        /// libm = Wasmer::ImportObject.new
+        /// libm.register "env", { :two => two }
+        ///
+        /// import_object = libc.merge libm
+        /// assert { import_object.to_h == { "env" => { "one" => one, "two" => two } } }
+        /// # "#); }
+        /// ```
+        pub fn merge(&self, other: ImportObject) -> ImportObject {
+            x!()
+        }
+
+        /// Returns all the registered externs as a `Hash` of
+        /// namespaces, each namespace being a `Hash` of externs
+        /// indexed by name.
+        pub fn to_h(&self) -> Hash<String, Hash<String, Any>> {
+            x!()
+        }
+
+        /// Lists the imports of the module that are missing or have
+        /// an incompatible type in the registered namespaces (the
+        /// resolver block is not called), as
+        /// `Wasmer::LinkError`s (see [`Instance::new`]) that aren't
+        /// raised. An empty array means that the module can be
+        /// instantiated with this import object.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// store = Wasmer::Store.new
+        /// module_ = Wasmer::Module.new(
+        ///   store,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "env" "log" (func (param i32)))
+        ///     (import "env" "global" (global i32)))
+        ///   WAST
+        /// )
+        ///
+        /// import_object = Wasmer::ImportObject.new
+        /// import_object.register "env", { :global => Wasmer::Global.new(store, Wasmer::Value.i64(7), false) }
+        ///
+        /// errors = import_object.validate_for module_
+        ///
+        /// assert { errors.map(&:name) == ["log", "global"] }
+        /// assert { errors[0].provided.nil? }
+        /// assert { errors[1].provided.type == Wasmer::Type::I64 }
+        /// # "#); }
+        /// ```
+        pub fn validate_for(&self, module: Module) -> Array<Any> {
+            x!()
+        }
+    }
+
+    /// A `Linker` instantiates a graph of modules by name. Each
+    /// module is defined under the name of the namespace it provides
+    /// to the other modules: when a module imports from this
+    /// namespace, the providing module is instantiated first, and
+    /// its exports are used as imports. Each module is instantiated
+    /// once, so that its instance is shared by all the modules
+    /// importing from it.
+    ///
+    /// Namespaces that aren't provided by a module, like WASI, are
+    /// registered with [`Linker::register`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { rutie_test::test_ruby!(r#"
+    /// store = Wasmer::Store.new
+    /// libc = Wasmer::Module.new(
+    ///   store,
+    ///   (<<~WAST)
+    ///   (module
+    ///     (memory (export "memory") 1)
+    ///     (func (export "store") (param i32 i32)
+    ///       local.get 0
+    ///       local.get 1
+    ///       i32.store))
+    ///   WAST
+    /// )
+    /// app = Wasmer::Module.new(
+    ///   store,
+    ///   (<<~WAST)
+    ///   (module
+    ///     (import "libc" "store" (func $store (param i32 i32)))
+    ///     (func (export "run")
+    ///       i32.const 0
+    ///       i32.const 42
+    ///       call $store))
+    ///   WAST
+    /// )
+    ///
+    /// linker = Wasmer::Linker.new
+    /// linker.define("libc", libc).define("app", app)
+    ///
+    /// linker.instantiate("app").exports.run.()
+    ///
+    /// assert { linker["libc"].exports.memory.uint8_view(0)[0] == 42 }
+    /// # "#); }
+    /// ```
+    pub struct Linker;
+
+    impl Linker {
+        /// Creates a new, empty, `Linker`.
+        pub fn new() -> Self {
+            x!()
+        }
+
+        /// Defines the module providing the namespace `name`. An
+        /// `ArgumentError` is raised if it is already defined.
+        pub fn define(&mut self, name: String, module: Module) -> Self {
+            x!()
+        }
+
+        /// Registers a namespace that isn't provided by a module, as
+        /// a `Hash` of externs or as [`Exports`], like
+        /// [`ImportObject::register`]. A namespace provided by a
+        /// module takes precedence.
+        pub fn register(&mut self, namespace_name: String, namespace: Any) -> Self {
+            x!()
+        }
+
+        /// Returns the names of the defined modules.
+        pub fn modules(&self) -> Array<String> {
+            x!()
+        }
+
+        /// Instantiates the module `name`, after the modules it
+        /// imports from, recursively, and returns its [`Instance`].
+        ///
+        /// An `ArgumentError` is raised if the module isn't defined,
+        /// and a `Wasmer::InstantiationError` is raised if the
+        /// modules import from each other in a cycle.
+        pub fn instantiate(&mut self, name: String) -> Instance {
+            x!()
+        }
+
+        /// Returns the instance of the module `name` if it has been
+        /// instantiated, `nil` otherwise. It is called with
+        /// `linker[name]`.
+        pub fn get(&self, name: String) -> Option<Instance> {
             x!()
         }
     }
@@ -1071,6 +2164,18 @@ pub mod Wasmer {
         pub fn r#type(&self) -> FunctionType {
             x!()
         }
+
+        /// Checks whether two `Function` objects represent the same
+        /// WebAssembly function.
+        pub fn eq(&self, other: Any) -> Boolean {
+            x!()
+        }
+
+        /// Returns a hash code consistent with `==`, so that the
+        /// object can be used as a `Hash` key.
+        pub fn hash(&self) -> Integer {
+            x!()
+        }
     }
 
     /// A WebAssembly memory instance.
@@ -1192,6 +2297,31 @@ pub mod Wasmer {
         pub fn int32_view(&self) -> Int32View {
             x!()
         }
+
+        /// Checks whether two `Memory` objects represent the same
+        /// WebAssembly memory.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new Wasmer::Store.new, "(module (memory (export \"mem\") 1))"
+        /// exports = Wasmer::Instance.new(module_, nil).exports
+        ///
+        /// assert { exports.mem == exports.mem }
+        /// assert { exports.mem.equal? exports.mem }
+        /// assert { exports.mem.hash == exports["mem"].hash }
+        /// # "#); }
+        /// ```
+        pub fn eq(&self, other: Any) -> Boolean {
+            x!()
+        }
+
+        /// Returns a hash code consistent with `==`, so that the
+        /// object can be used as a `Hash` key.
+        pub fn hash(&self) -> Integer {
+            x!()
+        }
     }
 
     pub struct Uint8View;
@@ -1266,6 +2396,18 @@ pub mod Wasmer {
         pub fn r#type(&self) -> GlobalType {
             x!()
         }
+
+        /// Checks whether two `Global` objects represent the same
+        /// WebAssembly global.
+        pub fn eq(&self, other: Any) -> Boolean {
+            x!()
+        }
+
+        /// Returns a hash code consistent with `==`, so that the
+        /// object can be used as a `Hash` key.
+        pub fn hash(&self) -> Integer {
+            x!()
+        }
     }
 
     /// A WebAssembly table instance.
@@ -1284,6 +2426,18 @@ pub mod Wasmer {
         pub fn new(store: Store, table_type: TableType, initia_value: Value) -> Self {
             x!()
         }
+
+        /// Checks whether two `Table` objects represent the same
+        /// WebAssembly table.
+        pub fn eq(&self, other: Any) -> Boolean {
+            x!()
+        }
+
+        /// Returns a hash code consistent with `==`, so that the
+        /// object can be used as a `Hash` key.
+        pub fn hash(&self) -> Integer {
+            x!()
+        }
     }
 
     /// Represents a WebAssembly value of a specific type.
@@ -1347,6 +2501,84 @@ pub mod Wasmer {
         }
     }
 
+    /// Wasmer's [Emscripten] implementation, to run legacy modules
+    /// compiled with Emscripten.
+    ///
+    /// Like WASI, Emscripten is a bunch of imports, generated with
+    /// [`generate_import_object`](Emscripten::generate_import_object).
+    /// These imports need to be set up once the module is
+    /// instantiated, which [`run`](Emscripten::run) does.
+    ///
+    /// [Emscripten]: https://emscripten.org/
+    pub mod Emscripten {
+        use super::*;
+        use crate::doc::Ruby::*;
+
+        /// Checks whether a [`Module`] has been compiled with
+        /// Emscripten, by looking at its imports.
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new Wasmer::Store.new, "(module)"
+        ///
+        /// assert { not Wasmer::Emscripten.detect?(module_) }
+        /// # "#); }
+        /// ```
+        pub fn detect(module: Module) -> Boolean {
+            x!()
+        }
+
+        /// Creates an [`ImportObject`] with the Emscripten imports of
+        /// a [`Module`]. The memory and the table of the module are
+        /// sized after its imports.
+        ///
+        /// The imports that use the memory, or the global
+        /// constructors of the module, only work once they are set
+        /// up after the instantiation: use [`run`](Emscripten::run)
+        /// to run such a module.
+        pub fn generate_import_object(store: Store, module: Module) -> ImportObject {
+            x!()
+        }
+
+        /// Instantiates a [`Module`] with the Emscripten imports, sets
+        /// up the Emscripten environment (its memory and the global
+        /// constructors of the module), then calls the `main`
+        /// function with `arguments` (an `Array` of `String`), or the
+        /// `entrypoint` function without argument. Returns the
+        /// [`Instance`].
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// module_ = Wasmer::Module.new(
+        ///   Wasmer::Store.new,
+        ///   (<<~WAST)
+        ///   (module
+        ///     (import "env" "memory" (memory 256 256))
+        ///     (import "env" "table" (table 0 funcref))
+        ///     (global $counter (export "counter") (mut i32) (i32.const 0))
+        ///     (func (export "run")
+        ///       i32.const 1
+        ///       global.set $counter))
+        ///   WAST
+        /// )
+        /// instance = Wasmer::Emscripten.run module_, [], entrypoint: "run"
+        ///
+        /// assert { instance.exports.counter.value == 1 }
+        /// # "#); }
+        /// ```
+        pub fn run(
+            module: Module,
+            arguments: Option<Array<String>>,
+            entrypoint: Option<String>,
+        ) -> Instance {
+            x!()
+        }
+    }
+
     /// Wasmer's [WASI] implementation.
     ///
     /// From the user perspective, WASI is a bunch of imports. To
@@ -1391,6 +2623,9 @@ pub mod Wasmer {
         use crate::doc::Ruby::*;
 
         /// Represents a WASI version.
+        ///
+        /// Where a version is expected, it can also be given as a
+        /// symbol: `:latest`, `:snapshot0` or `:snapshot1`.
         #[allow(non_camel_case_types)]
         pub enum Version {
             LATEST_VERSION,
@@ -1440,12 +2675,32 @@ pub mod Wasmer {
                 x!()
             }
 
-            /// Preopen a directory with a different name exposed to the WASI.
+            /// Preopen a directory.
             ///
-            /// This opens the given directories at the virtual root,
-            /// `/`, and allows the WASI module to read and write to the
-            /// given directories.
-            pub fn preopen_directory(&mut self, alias: String, value: String) -> Self {
+            /// This opens the given directory at the virtual root,
+            /// `/`. By default, the WASI module can read and write to
+            /// the given directory, and create files in it. The
+            /// `read`, `write` and `create` keyword arguments
+            /// restrict these permissions, e.g. `write: false`
+            /// prevents the WASI module from modifying the
+            /// directory. `create` follows `write` when omitted, and
+            /// cannot be `true` if `write` is `false`.
+            ///
+            /// # Example
+            ///
+            /// ```rust,ignore
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .preopen_directory("data", read: true, write: false)
+            /// # "#); }
+            /// ```
+            pub fn preopen_directory(
+                &mut self,
+                directory: String,
+                read: Option<Boolean>,
+                write: Option<Boolean>,
+                create: Option<Boolean>,
+            ) -> Self {
                 x!()
             }
 
@@ -1459,7 +2714,18 @@ pub mod Wasmer {
             }
 
             /// Preopen a directory with a different name exposed to the WASI.
-            pub fn map_directory(&mut self, alias: String, value: String) -> Self {
+            ///
+            /// The `read`, `write` and `create` keyword arguments
+            /// restrict the permissions of the WASI module, see
+            /// [`StateBuilder::preopen_directory`].
+            pub fn map_directory(
+                &mut self,
+                alias: String,
+                directory: String,
+                read: Option<Boolean>,
+                write: Option<Boolean>,
+                create: Option<Boolean>,
+            ) -> Self {
                 x!()
             }
 
@@ -1468,16 +2734,173 @@ pub mod Wasmer {
                 x!()
             }
 
+            /// Captures the standard output of the guest, instead of
+            /// writing it to the standard output of the host. The
+            /// captured bytes are read with [`Environment::stdout`].
+            pub fn capture_stdout(&mut self) -> Self {
+                x!()
+            }
+
+            /// Captures the standard error of the guest, instead of
+            /// writing it to the standard error of the host. The
+            /// captured bytes are read with [`Environment::stderr`].
+            pub fn capture_stderr(&mut self) -> Self {
+                x!()
+            }
+
+            /// Sets the standard input of the guest. It is either a
+            /// `String`, or an `IO` (or any object with a
+            /// `read(length)` method, like `StringIO`), which is read
+            /// lazily, when the guest reads its standard input.
+            pub fn stdin(&mut self, stdin: Any) -> Self {
+                x!()
+            }
+
+            /// Mounts an in-memory filesystem, [`MemFS`], at
+            /// `guest_path` (an absolute path) in the guest. The
+            /// files the guest creates below `guest_path` are written
+            /// to the filesystem, and can be read from Ruby after
+            /// the run.
+            pub fn mount(&mut self, guest_path: String, memfs: MemFS) -> Self {
+                x!()
+            }
+
+            /// Replaces the clocks of the host, read by the
+            /// `clock_time_get` WASI function, to make the time
+            /// deterministic. Either all the clocks return a fixed
+            /// time, with `clock(:fixed, time)`, or they call the
+            /// given block with the clock name (`:realtime`,
+            /// `:monotonic`, `:process_cputime` or `:thread_cputime`).
+            /// A time is an `Integer` of nanoseconds, or a `Time`.
+            ///
+            /// # Example
+            ///
+            /// ```rust,ignore
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .clock(:fixed, Time.utc(2021, 1, 1))
+            ///
+            /// ticks = 0
+            /// Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .clock { |clock| ticks += 1_000_000 }
+            /// # "#); }
+            /// ```
+            pub fn clock(&mut self, kind: Option<Symbol>, time: Option<Any>) -> Self {
+                x!()
+            }
+
+            /// Replaces the random numbers of the host, read by the
+            /// `random_get` WASI function, by a pseudo-random sequence
+            /// generated from `seed`, so that two runs with the same
+            /// seed get the same numbers. The sequence isn't
+            /// cryptographically secure.
+            pub fn random_seed(&mut self, seed: Integer) -> Self {
+                x!()
+            }
+
+            /// Traces the WASI calls of the guest, like `strace`. Each
+            /// call is either given to the block, with the name of the
+            /// syscall (a `Symbol`), its decoded arguments (a `Hash`)
+            /// and its errno (`nil` if the call didn't return, e.g.
+            /// `proc_exit`), or written as a line to the given `IO`.
+            ///
+            /// Paths are decoded as strings, I/O vectors as their total
+            /// number of bytes, and the integers written by the call
+            /// (e.g. `nwritten` or `opened_fd`) are read after it,
+            /// or `nil` if it failed. A line looks like `fd_write(fd:
+            /// 1, iovs: 13, nwritten: 13) = 0`, or `path_open(…) = 44
+            /// (ENOENT)` on error.
+            ///
+            /// # Example
+            ///
+            /// ```rust,ignore
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .trace($stderr)
+            ///
+            /// Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .trace { |syscall, arguments, errno| p [syscall, arguments, errno] }
+            /// # "#); }
+            /// ```
+            pub fn trace(&mut self, io: Option<Any>) -> Self {
+                x!()
+            }
+
             /// Produces a WASI [`Environment`] based on this state builder.
             pub fn finalize(&mut self) -> Environment {
                 x!()
             }
         }
 
+        /// An in-memory filesystem, to be mounted in a guest with
+        /// [`StateBuilder::mount`].
+        ///
+        /// Paths are absolute within the filesystem; a relative path
+        /// is relative to its root.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # fn main() { rutie_test::test_ruby!(r#"
+        /// memfs = Wasmer::Wasi::MemFS.new
+        /// memfs.mkdir "/input"
+        /// memfs.write_file "/input/data.txt", "Hello, World!"
+        ///
+        /// wasi_env = Wasmer::Wasi::StateBuilder.new("test-program")
+        ///              .mount("/data", memfs)
+        ///              .finalize
+        /// import_object = wasi_env.generate_import_object store, wasi_version
+        /// instance = Wasmer::Instance.new module_, import_object
+        ///
+        /// instance.exports._start.()
+        ///
+        /// puts memfs.list "/"
+        /// # "#); }
+        /// ```
+        pub struct MemFS;
+
+        impl MemFS {
+            pub fn new() -> Self {
+                x!()
+            }
+
+            /// Writes `content` to the file at `path`, creating the
+            /// file, or truncating it if it exists. The parent
+            /// directory must exist.
+            pub fn write_file(&self, path: String, content: String) -> Self {
+                x!()
+            }
+
+            /// Reads the content of the file at `path`. A
+            /// `RuntimeError` is raised if the file does not exist.
+            pub fn read_file(&self, path: String) -> String {
+                x!()
+            }
+
+            /// Creates a directory at `path`. The parent directory
+            /// must exist.
+            pub fn mkdir(&self, path: String) -> Self {
+                x!()
+            }
+
+            /// Lists the names of the entries of the directory at
+            /// `path`, sorted. The default `path` is the root, `/`.
+            pub fn list(&self, path: Option<String>) -> Array<String> {
+                x!()
+            }
+        }
+
         /// The environment provided to the WASI imports.
         ///
         /// To build it, use [`StateBuilder`]. See
         /// [`StateBuilder::finalize`] to learn more.
+        ///
+        /// The memory used by the WASI imports is always the memory
+        /// exported by the instance as `memory`: it cannot be set
+        /// with a `set_memory` method. With `wasmer-wasi` 2.1, the
+        /// memory of the environment can only be initialized by the
+        /// instance itself, so a module importing its memory must
+        /// also export it.
         pub struct Environment;
 
         impl Environment {
@@ -1485,16 +2908,180 @@ pub mod Wasmer {
             /// [`Environment`]. The import object will be different
             /// according to the WASI version.
             ///
-            /// Use the [`Version`] enum to use a specific WASI
-            /// version, or use [`get_version`] to read the WASI
-            /// version from a [`Module`].
+            /// Use the [`Version`] enum, or its symbol (e.g.
+            /// `:snapshot1`), to use a specific WASI version, or give
+            /// the [`Module`] itself to detect its WASI version, like
+            /// [`get_version`] does. A `TypeError` is raised if the
+            /// module doesn't import any WASI namespace.
+            ///
+            /// The `extra` namespaces, given as a `Hash` of namespaces
+            /// like in [`ImportObject::register`], are merged with the
+            /// WASI ones: a namespace that already exists is extended
+            /// rather than replaced, and a `NameError` is raised if an
+            /// import is registered twice.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// store = Wasmer::Store.new
+            /// module_ = Wasmer::Module.new store, '(module (import "wasi_unstable" "sched_yield" (func (result i32))))'
+            ///
+            /// import_object = Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .finalize
+            ///   .generate_import_object(store, module_)
+            ///
+            /// assert { import_object.contains_namespace? "wasi_unstable" }
+            ///
+            /// log = Wasmer::Function.new store, ->(x) { }, Wasmer::FunctionType.new([Wasmer::Type::I32], [])
+            ///
+            /// import_object = Wasmer::Wasi::StateBuilder.new("test-program")
+            ///   .finalize
+            ///   .generate_import_object(store, Wasmer::Wasi::Version::SNAPSHOT1, extra: { "env" => { "log" => log } })
+            ///
+            /// assert { import_object.contains_namespace? "env" }
+            /// assert { import_object.contains_namespace? "wasi_snapshot_preview1" }
+            /// # "#); }
+            /// ```
             pub fn generate_import_object(
                 &self,
                 store: Store,
-                wasi_version: Version,
+                wasi_version: Any,
+                extra: Option<Hash<String, Hash<String, Any>>>,
             ) -> ImportObject {
                 x!()
             }
+
+            /// Runs a WASI command: instantiates `module` with this
+            /// environment, calls its `_start` function, and returns
+            /// the exit code of the program, which is `0` if
+            /// `_start` returns normally, or the code given to
+            /// `proc_exit`.
+            ///
+            /// A `TypeError` is raised if the module isn't a WASI
+            /// module, a `NameError` if it doesn't export `_start`,
+            /// and a `RuntimeError` if the program traps.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// module_ = Wasmer::Module.new(
+            ///   Wasmer::Store.new,
+            ///   (<<~WAST)
+            ///   (module
+            ///     (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+            ///     (memory (export "memory") 1)
+            ///     (func (export "_start")
+            ///       (call $proc_exit (i32.const 42))))
+            ///   WAST
+            /// )
+            /// wasi_env = Wasmer::Wasi::StateBuilder.new("test-program").finalize
+            ///
+            /// assert { wasi_env.run(module_) == 42 }
+            /// # "#); }
+            /// ```
+            pub fn run(&self, module: Module) -> Integer {
+                x!()
+            }
+
+            /// Returns the arguments given to the program, without
+            /// the program name.
+            pub fn arguments(&self) -> Array<String> {
+                x!()
+            }
+
+            /// Replaces the arguments given to the program, for the
+            /// next runs. Arguments must not contain the nul (`0x0`)
+            /// byte.
+            pub fn set_arguments(&self, arguments: Array<String>) {
+                x!()
+            }
+
+            /// Returns the environment variables given to the
+            /// program.
+            pub fn environment_variables(&self) -> Hash<String, String> {
+                x!()
+            }
+
+            /// Replaces the environment variables given to the
+            /// program, for the next runs. Keys must not contain the
+            /// byte `=` (`0x3d`), and keys and values must not contain
+            /// the nul (`0x0`) byte.
+            pub fn set_environment_variables(&self, pairs: Hash<String, String>) {
+                x!()
+            }
+
+            /// Returns the file descriptors opened by the program,
+            /// with their name, e.g. `{0 => "stdin", 1 => "stdout",
+            /// 2 => "stderr", 3 => "/", 4 => "."}`, where `/` is the
+            /// virtual root holding the preopened directories.
+            pub fn file_descriptors(&self) -> Hash<Integer, String> {
+                x!()
+            }
+
+            /// Returns the preopened directories, with their guest
+            /// name and their host path.
+            pub fn preopened_directories(&self) -> Hash<String, String> {
+                x!()
+            }
+
+            /// Reads the file at `guest_path`, as seen by the program,
+            /// from the preopened directories, including the
+            /// filesystems mounted with [`StateBuilder::mount`]. A
+            /// `NameError` is raised if the path isn't in a preopened
+            /// directory, and a `RuntimeError` if the file cannot be
+            /// read.
+            ///
+            /// # Example
+            ///
+            /// ```rust,ignore
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// wasi_env = Wasmer::Wasi::StateBuilder.new("test-program")
+            ///              .map_directory("output", "/tmp/output")
+            ///              .finalize
+            ///
+            /// wasi_env.run module_
+            ///
+            /// puts wasi_env.read_file("output/result.txt")
+            /// # "#); }
+            /// ```
+            pub fn read_file(&self, guest_path: String) -> String {
+                x!()
+            }
+
+            /// Returns the bytes written by the guest to its standard
+            /// output since the last call, if it is captured with
+            /// [`StateBuilder::capture_stdout`]. It can be called
+            /// during or after the execution. A `RuntimeError` is
+            /// raised if the standard output isn't captured.
+            ///
+            /// # Example
+            ///
+            /// ```rust,ignore
+            /// # fn main() { rutie_test::test_ruby!(r#"
+            /// wasi_env = Wasmer::Wasi::StateBuilder.new("test-program")
+            ///              .capture_stdout
+            ///              .finalize
+            /// import_object = wasi_env.generate_import_object store, wasi_version
+            /// instance = Wasmer::Instance.new module_, import_object
+            ///
+            /// instance.exports._start.()
+            ///
+            /// puts wasi_env.stdout
+            /// # "#); }
+            /// ```
+            pub fn stdout(&self) -> String {
+                x!()
+            }
+
+            /// Returns the bytes written by the guest to its standard
+            /// error since the last call, if it is captured with
+            /// [`StateBuilder::capture_stderr`]. See
+            /// [`Environment::stdout`].
+            pub fn stderr(&self) -> String {
+                x!()
+            }
         }
 
         /// Detect the version of WASI being used based on the import
@@ -1505,7 +3092,16 @@ pub mod Wasmer {
         /// at least one WASI namespace exits to detect the
         /// version. Note that the strict detection is faster than the
         /// non-strict one.
-        pub fn get_version(module: Module, strict: Boolean) -> Version {
+        ///
+        /// The version is returned as a [`Version`] constant, or as a
+        /// symbol (e.g. `:snapshot1`) with `as: :symbol`. Both forms
+        /// are accepted by [`Environment::generate_import_object`].
+        /// `nil` is returned if the module isn't a WASI module.
+        pub fn get_version(
+            module: Module,
+            strict: Boolean,
+            r#as: Option<Symbol>,
+        ) -> Option<Version> {
             x!()
         }
     }
@@ -0,0 +1,116 @@
+use crate::{
+    error::{to_ruby_err, RuntimeError},
+    import_object::ImportObject,
+    module::Module,
+    prelude::*,
+    store::Store,
+};
+use rutie::{AnyObject, Boolean};
+
+#[rubyfunction]
+pub fn detect(module: &Module) -> RubyResult<Boolean> {
+    Ok(Boolean::new(wasmer_emscripten::is_emscripten_module(
+        module.inner(),
+    )))
+}
+
+#[rubyfunction]
+pub fn generate_import_object(store: &Store, module: &Module) -> RubyResult<AnyObject> {
+    let mut globals = wasmer_emscripten::EmscriptenGlobals::new(store.inner(), module.inner())
+        .map_err(to_ruby_err::<RuntimeError, _>)?;
+    let mut environment = wasmer_emscripten::EmEnv::new(&globals.data, Default::default());
+
+    let import_object =
+        wasmer_emscripten::generate_emscripten_env(store.inner(), &mut globals, &mut environment);
+
+    Ok(ImportObject::ruby_new(ImportObject::raw_new(import_object)))
+}
+
+pub(crate) mod ruby_emscripten_extra {
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, RuntimeError},
+        import_object::ImportObject,
+        instance::Instance,
+        keywords::Keywords,
+        module::RubyModule,
+        prelude::*,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Array, Object, RString,
+    };
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn run(argc: Argc, argv: *const AnyObject, _module: AnyObject) -> AnyObject {
+        unwrap_or_raise(|| {
+            let module = Value::from(0);
+            let arguments = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("11:").as_ptr(),
+                    &module,
+                    &arguments,
+                    &keywords,
+                )
+            };
+
+            let module = AnyObject::from(module).try_convert_to::<RubyModule>()?;
+            let module = module.upcast();
+            let arguments = AnyObject::from(arguments);
+            let arguments = if arguments.is_nil() {
+                Vec::new()
+            } else {
+                arguments
+                    .try_convert_to::<Array>()?
+                    .into_iter()
+                    .map(|argument| Ok(argument.try_convert_to::<RString>()?.to_string()))
+                    .collect::<RubyResult<Vec<_>>>()?
+            };
+            let keywords = Keywords::new(AnyObject::from(keywords), &["entrypoint"])?;
+            let entrypoint = keywords
+                .get("entrypoint")
+                .map(|entrypoint| {
+                    entrypoint
+                        .try_convert_to::<RString>()
+                        .map(|entrypoint| entrypoint.to_string())
+                })
+                .transpose()?;
+
+            let store = module.inner().store();
+            let mut globals = wasmer_emscripten::EmscriptenGlobals::new(store, module.inner())
+                .map_err(to_ruby_err::<RuntimeError, _>)?;
+            let mut environment = wasmer_emscripten::EmEnv::new(&globals.data, Default::default());
+            let import_object =
+                wasmer_emscripten::generate_emscripten_env(store, &mut globals, &mut environment);
+
+            let instance = Instance::raw_new(
+                module,
+                &ImportObject::ruby_new(ImportObject::raw_new(import_object)),
+                true,
+                false,
+            )?;
+
+            // The environment only gets its memory, and the module
+            // only runs its global constructors, once instantiated.
+            wasmer_emscripten::run_emscripten_instance(
+                &mut instance.inner().clone(),
+                &mut environment,
+                &mut globals,
+                module.inner().name().unwrap_or(""),
+                arguments.iter().map(String::as_str).collect(),
+                entrypoint,
+            )
+            .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+            Ok(Instance::ruby_new(instance))
+        })
+    }
+}
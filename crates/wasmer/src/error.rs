@@ -1,6 +1,6 @@
 //! Functions to handle error or exception correctly.
 
-use rutie::{AnyException, Exception, VM};
+use rutie::{AnyException, AnyObject, Class, Exception, Module, Object, RString, VM};
 
 pub type RubyResult<T> = Result<T, AnyException>;
 
@@ -18,8 +18,29 @@ where
     }
 }
 
+/// Calls Ruby from code that an exception must not unwind through,
+/// e.g. a host function called by Wasmer: the exception raised by
+/// `function`, if any, is caught and returned.
+pub(crate) fn protect<Function>(function: Function) -> RubyResult<AnyObject>
+where
+    Function: FnMut() -> AnyObject,
+{
+    VM::protect(function).map_err(|_| {
+        VM::error_pop()
+            .unwrap_or_else(|| RuntimeError::new_exception("An unknown exception has been raised"))
+    })
+}
+
 pub trait ErrorType {
     fn name() -> &'static str;
+
+    fn class() -> Class {
+        Class::from_existing(Self::name())
+    }
+
+    fn new_exception(message: &str) -> AnyException {
+        AnyException::new(Self::name(), Some(message))
+    }
 }
 
 macro_rules! declare_error {
@@ -46,10 +67,80 @@ declare_error!(
     TypeError,
 );
 
+/// Declares error classes nested in the `Wasmer` module, with their
+/// parent class, and optionally some attributes (read-only).
+macro_rules! declare_wasmer_error {
+    ( $( $name:ident < $parent:ident $( { $( $attribute:ident ),* $(,)? } )? ),+ $(,)? ) => {
+        $(
+            pub struct $name;
+
+            impl ErrorType for $name {
+                fn name() -> &'static str {
+                    stringify!($name)
+                }
+
+                fn class() -> Class {
+                    Module::from_existing("Wasmer").get_nested_class(Self::name())
+                }
+
+                fn new_exception(message: &str) -> AnyException {
+                    let exception =
+                        Self::class().new_instance(&[RString::new_utf8(message).to_any_object()]);
+
+                    unsafe { exception.to::<AnyException>() }
+                }
+            }
+        )*
+
+        /// Defines the error classes in the `Wasmer` module.
+        pub(crate) fn define_wasmer_errors(module: &mut Module) {
+            $(
+                #[allow(unused_mut)]
+                let mut class =
+                    module.define_nested_class(stringify!($name), Some(&<$parent as ErrorType>::class()));
+
+                $( $( class.attr_reader(stringify!($attribute)); )* )?
+            )*
+        }
+    }
+}
+
+declare_wasmer_error!(
+    SignatureError < RuntimeError,
+    InstantiationError < RuntimeError,
+    LinkError
+        < InstantiationError {
+            module,
+            name,
+            expected,
+            provided
+        },
+    StartError < InstantiationError,
+);
+
 pub fn to_ruby_err<Type, Error>(error: Error) -> AnyException
 where
     Type: ErrorType,
     Error: ToString,
 {
-    AnyException::new(Type::name(), Some(error.to_string().as_ref()))
+    Type::new_exception(error.to_string().as_ref())
+}
+
+/// Like `to_ruby_err`, but also sets some attributes on the
+/// exception, e.g. `@module`.
+pub(crate) fn to_ruby_err_with_attributes<Type, Error>(
+    error: Error,
+    attributes: &[(&str, AnyObject)],
+) -> AnyException
+where
+    Type: ErrorType,
+    Error: ToString,
+{
+    let mut exception = to_ruby_err::<Type, _>(error);
+
+    for (name, value) in attributes {
+        exception.instance_variable_set(&format!("@{}", name), value.clone());
+    }
+
+    exception
 }
@@ -1,23 +1,131 @@
 use crate::{
-    error::{to_ruby_err, RuntimeError},
+    error::{to_ruby_err, NameError, RuntimeError, TypeError},
+    externals::to_ruby_object,
     prelude::*,
 };
-use rutie::{Boolean, Fixnum, Symbol};
-use std::convert::TryInto;
+use rutie::{
+    rubysys::class,
+    types::{Argc, CallbackPtr, Value},
+    util::str_to_cstring,
+    AnyObject, Array, Boolean, Class, Fixnum, Hash, NilClass, Object, RString, Symbol, VM,
+};
+use std::{cell::RefCell, collections::HashMap, convert::TryInto};
 
-#[rubyclass(module = "Wasmer")]
+#[rubyclass(module = "Wasmer", mark)]
 pub struct Exports {
     inner: wasmer::Exports,
+    /// The Ruby objects wrapping the externs, built once, on demand.
+    objects: RefCell<HashMap<String, AnyObject>>,
 }
 
 impl Exports {
     pub fn new(inner: wasmer::Exports) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            objects: RefCell::new(HashMap::new()),
+        }
     }
 
     pub(crate) fn inner(&self) -> &wasmer::Exports {
         &self.inner
     }
+
+    /// Returns the Ruby object wrapping the extern named `name`, so
+    /// that the same object is returned for the same extern.
+    fn ruby_object(&self, name: &str, r#extern: &wasmer::Extern) -> AnyObject {
+        if let Some(object) = self.objects.borrow().get(name) {
+            return object.clone();
+        }
+
+        // The object must be created while `objects` isn't borrowed,
+        // as it may trigger the garbage collector, which marks
+        // `objects`.
+        let object = to_ruby_object(r#extern);
+
+        self.objects
+            .borrow_mut()
+            .insert(name.to_string(), object.clone());
+
+        object
+    }
+
+    /// Returns the extern named `name`, or raises a `NameError` if it
+    /// does not exist.
+    fn get_extern(&self, name: &AnyObject) -> RubyResult<&wasmer::Extern> {
+        let name = to_extern_name(name)?;
+
+        self.inner()
+            .get_extern(&name)
+            .ok_or_else(|| to_ruby_err::<NameError, _>(format!("Export `{}` does not exist", name)))
+    }
+
+    /// Returns the extern named `name` as a Ruby object, or raises a
+    /// `TypeError` if it is not of the `expected_kind` kind.
+    fn get_typed_extern(&self, name: &AnyObject, expected_kind: &str) -> RubyResult<AnyObject> {
+        let r#extern = self.get_extern(name)?;
+
+        if extern_kind(r#extern) != expected_kind {
+            return Err(to_ruby_err::<TypeError, _>(format!(
+                "Export `{}` is a {}, not a {}",
+                to_extern_name(name)?,
+                extern_kind(r#extern),
+                expected_kind,
+            )));
+        }
+
+        Ok(self.ruby_object(&to_extern_name(name)?, r#extern))
+    }
+
+    /// Defines a singleton method on `target` for each exported
+    /// function, and returns their signatures. The functions named
+    /// like a method `target` already responds to (e.g. `send`,
+    /// `class` or `inspect`) are skipped.
+    pub(crate) fn define_methods_on(&self, target: &AnyObject) -> RubyResult<Vec<String>> {
+        let mut method_names = Vec::new();
+        let mut signatures = Vec::new();
+
+        for (name, r#extern) in self.inner().iter() {
+            let function_type = match r#extern {
+                wasmer::Extern::Function(function) => function.ty().clone(),
+                _ => continue,
+            };
+
+            let mut method_name = to_method_name(name);
+
+            while method_names.contains(&method_name) {
+                method_name.push('_');
+            }
+
+            if target.respond_to(&method_name) {
+                continue;
+            }
+
+            let method = to_lambda(
+                &self.ruby_object(name, r#extern),
+                function_type.params().len(),
+            );
+
+            unsafe {
+                target.send(
+                    "define_singleton_method",
+                    &[Symbol::new(&method_name).to_any_object(), method],
+                )
+            };
+
+            signatures.push(to_signature(&method_name, &function_type));
+            method_names.push(method_name);
+        }
+
+        Ok(signatures)
+    }
+}
+
+impl GcMark for Exports {
+    fn gc_mark(&self) {
+        for object in self.objects.borrow().values() {
+            object.gc_mark();
+        }
+    }
 }
 
 #[rubymethods]
@@ -38,18 +146,229 @@ impl Exports {
                 .map_err(to_ruby_err::<RuntimeError, _>)?,
         ))
     }
+
+    pub fn get(&self, name: &AnyObject) -> RubyResult<AnyObject> {
+        let name = to_extern_name(name)?;
+
+        Ok(self.inner().get_extern(&name).map_or_else(
+            || NilClass::new().to_any_object(),
+            |r#extern| self.ruby_object(&name, r#extern),
+        ))
+    }
+
+    pub fn each(&self) -> RubyResult<AnyObject> {
+        if !VM::is_block_given() {
+            return Ok(unsafe {
+                _ruby_self.send("enum_for", &[Symbol::new("each").to_any_object()])
+            });
+        }
+
+        for (name, r#extern) in self.inner().iter() {
+            let mut pair = Array::with_capacity(2);
+            pair.push(RString::new_utf8(name));
+            pair.push(self.ruby_object(name, r#extern));
+
+            VM::yield_object(pair);
+        }
+
+        Ok(_ruby_self.to_any_object())
+    }
+
+    pub fn keys(&self) -> RubyResult<Array> {
+        Ok(self
+            .inner()
+            .iter()
+            .map(|(name, _)| RString::new_utf8(name).to_any_object())
+            .collect())
+    }
+
+    pub fn to_h(&self) -> RubyResult<Hash> {
+        let mut hash = Hash::new();
+
+        for (name, r#extern) in self.inner().iter() {
+            hash.store(RString::new_utf8(name), self.ruby_object(name, r#extern));
+        }
+
+        Ok(hash)
+    }
+
+    pub fn to_module(&self) -> RubyResult<AnyObject> {
+        let module = Class::from_existing("Module").new_instance(&[]);
+        let signatures = self.define_methods_on(&module)?;
+        let inspect = RString::new_utf8(&format!("#<Wasmer exports: {}>", signatures.join(", ")));
+
+        // An `inspect` export has been skipped, since a module
+        // already responds to `inspect`.
+        let inspect_method = to_proc(&inspect.to_any_object(), "itself");
+
+        unsafe {
+            module.send(
+                "define_singleton_method",
+                &[Symbol::new("inspect").to_any_object(), inspect_method],
+            )
+        };
+
+        Ok(module)
+    }
 }
 
-pub(crate) mod ruby_exports_extra {
-    use crate::{
-        error::{to_ruby_err, unwrap_or_raise, ArgumentError, NameError},
-        externals::{Function, Global, Memory, Table},
+/// Reads an export name, given as a `String` or a `Symbol`.
+fn to_extern_name(name: &AnyObject) -> RubyResult<String> {
+    if let Ok(name) = name.try_convert_to::<RString>() {
+        Ok(name.to_str().to_string())
+    } else if let Ok(name) = name.try_convert_to::<Symbol>() {
+        Ok(name.to_str().to_string())
+    } else {
+        Err(to_ruby_err::<TypeError, _>(format!(
+            "Export name has an invalid type `{:?}` (expects `String` or `Symbol`)",
+            name.ty()
+        )))
+    }
+}
+
+/// Turns an export name into a valid Ruby method name, by replacing
+/// the invalid characters by `_`, e.g. `my-func` becomes `my_func`,
+/// and by prefixing a leading digit with `_`.
+fn to_method_name(name: &str) -> String {
+    let mut method_name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    if method_name.is_empty() || method_name.starts_with(|c: char| c.is_ascii_digit()) {
+        method_name.insert(0, '_');
+    }
+
+    method_name
+}
+
+/// Name of the hidden instance variable holding the function called
+/// by a fixed-arity `call` method. It doesn't start with `@`, so
+/// that it isn't reachable from Ruby.
+const FUNCTION_VARIABLE: &str = "__wasmer_function";
+
+/// Calls the function held by `holder`, see `to_lambda`.
+fn call_held_function(holder: Value, arguments: &[AnyObject]) -> Value {
+    let function = AnyObject::from(holder).instance_variable_get(FUNCTION_VARIABLE);
+
+    unsafe { function.send("call", arguments) }.value()
+}
+
+/// Defines `extern "C"` functions with a fixed number of parameters,
+/// calling the function held by their receiver, and a function
+/// returning the one of a given arity.
+macro_rules! fixed_arity_calls {
+    ($( $arity:literal => $name:ident ( $( $argument:ident ),* ) ),* $(,)?) => {
+        $(
+            extern "C" fn $name(holder: Value $( , $argument: Value )*) -> Value {
+                call_held_function(holder, &[ $( AnyObject::from($argument) ),* ])
+            }
+        )*
+
+        fn fixed_arity_call(arity: usize) -> Option<CallbackPtr> {
+            Some(match arity {
+                $( $arity => $name as CallbackPtr, )*
+                _ => return None,
+            })
+        }
     };
+}
+
+// Ruby supports up to 15 parameters for a method defined in C.
+fixed_arity_calls! {
+    0 => call_0(),
+    1 => call_1(a0),
+    2 => call_2(a0, a1),
+    3 => call_3(a0, a1, a2),
+    4 => call_4(a0, a1, a2, a3),
+    5 => call_5(a0, a1, a2, a3, a4),
+    6 => call_6(a0, a1, a2, a3, a4, a5),
+    7 => call_7(a0, a1, a2, a3, a4, a5, a6),
+    8 => call_8(a0, a1, a2, a3, a4, a5, a6, a7),
+    9 => call_9(a0, a1, a2, a3, a4, a5, a6, a7, a8),
+    10 => call_10(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9),
+    11 => call_11(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10),
+    12 => call_12(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11),
+    13 => call_13(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12),
+    14 => call_14(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12, a13),
+    15 => call_15(a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12, a13, a14),
+}
+
+/// Builds a lambda calling `function`, with one parameter per
+/// parameter of the function, so that Ruby checks the arity. The
+/// lambda is the `call` method, defined in C with a fixed arity, of
+/// an object holding the function. Past 15 parameters, the lambda
+/// takes any number of arguments.
+fn to_lambda(function: &AnyObject, arity: usize) -> AnyObject {
+    let callback = match fixed_arity_call(arity) {
+        Some(callback) => callback,
+        None => return to_proc(function, "call"),
+    };
+
+    let mut holder = Class::from_existing("Object").new_instance(&[]);
+    holder.instance_variable_set(FUNCTION_VARIABLE, function.clone());
+
+    unsafe {
+        class::rb_define_singleton_method(
+            holder.value(),
+            str_to_cstring("call").as_ptr(),
+            callback,
+            arity as Argc,
+        )
+    };
+
+    to_proc(&holder, "call")
+}
+
+/// Builds a lambda calling the `method_name` method of `receiver`,
+/// i.e. `receiver.method(method_name).to_proc`.
+fn to_proc(receiver: &AnyObject, method_name: &str) -> AnyObject {
+    unsafe {
+        receiver
+            .send("method", &[Symbol::new(method_name).to_any_object()])
+            .send("to_proc", &[])
+    }
+}
+
+/// Formats the signature of a function, e.g. `sum(i32, i32) -> i32`.
+fn to_signature(name: &str, function_type: &wasmer::FunctionType) -> String {
+    let format_types = |types: &[wasmer::Type]| {
+        types
+            .iter()
+            .map(|ty| format!("{:?}", ty).to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let results = function_type.results();
+
+    format!(
+        "{}({}) -> {}",
+        name,
+        format_types(function_type.params()),
+        if results.len() == 1 {
+            format_types(results)
+        } else {
+            format!("({})", format_types(results))
+        }
+    )
+}
+
+fn extern_kind(r#extern: &wasmer::Extern) -> &'static str {
+    match r#extern {
+        wasmer::Extern::Function(_) => "function",
+        wasmer::Extern::Memory(_) => "memory",
+        wasmer::Extern::Global(_) => "global",
+        wasmer::Extern::Table(_) => "table",
+    }
+}
+
+pub(crate) mod ruby_exports_extra {
+    use crate::error::{to_ruby_err, unwrap_or_raise, ArgumentError, NameError};
     use rutie::{
         rubysys::class,
         types::{Argc, Value},
         util::str_to_cstring,
-        AnyObject, Array, Object, Symbol,
+        AnyObject, Array, Object, RString, Symbol,
     };
     use rutie_derive::UpcastRubyClass;
 
@@ -83,18 +402,7 @@ pub(crate) mod ruby_exports_extra {
             }
 
             Ok(match exports.inner().get_extern(extern_name) {
-                Some(wasmer::Extern::Function(function)) => {
-                    Function::ruby_new(Function::raw_new(function.clone())).to_any_object()
-                }
-                Some(wasmer::Extern::Memory(memory)) => {
-                    Memory::ruby_new(Memory::raw_new(memory.clone())).to_any_object()
-                }
-                Some(wasmer::Extern::Global(global)) => {
-                    Global::ruby_new(Global::raw_new(global.clone())).to_any_object()
-                }
-                Some(wasmer::Extern::Table(table)) => {
-                    Table::ruby_new(Table::raw_new(table.clone())).to_any_object()
-                }
+                Some(r#extern) => exports.ruby_object(extern_name, r#extern),
                 None => {
                     return Err(to_ruby_err::<NameError, _>(format!(
                         "Export `{}` does not exist",
@@ -104,4 +412,52 @@ pub(crate) mod ruby_exports_extra {
             })
         })
     }
+
+    /// Defines a typed accessor, e.g. `exports.memory("name")`. Without
+    /// argument, it behaves like `method_missing`, so that an export
+    /// named like the accessor (e.g. `exports.memory`) is still
+    /// reachable.
+    macro_rules! typed_accessor {
+        ($name:ident) => {
+            #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+            pub extern "C" fn $name(
+                argc: Argc,
+                argv: *const AnyObject,
+                itself: super::RubyExports,
+            ) -> AnyObject {
+                unwrap_or_raise(|| {
+                    let name = Value::from(0);
+
+                    unsafe {
+                        let argv_pointer = argv as *const Value;
+
+                        class::rb_scan_args(
+                            argc,
+                            argv_pointer,
+                            str_to_cstring("01").as_ptr(),
+                            &name,
+                        )
+                    };
+
+                    let exports = itself.upcast();
+                    let name = AnyObject::from(name);
+
+                    if name.is_nil() {
+                        let name = stringify!($name);
+
+                        exports
+                            .get_extern(&RString::new_utf8(name).to_any_object())
+                            .map(|r#extern| exports.ruby_object(name, r#extern))
+                    } else {
+                        exports.get_typed_extern(&name, stringify!($name))
+                    }
+                })
+            }
+        };
+    }
+
+    typed_accessor!(function);
+    typed_accessor!(memory);
+    typed_accessor!(global);
+    typed_accessor!(table);
 }
@@ -1,19 +1,95 @@
 use crate::{
     error::{to_ruby_err, TypeError},
+    externals::vm_address,
     prelude::*,
     store::Store,
     types::FunctionType,
     values::{to_ruby_object, to_wasm_value},
 };
-use rutie::{util::is_method, AnyObject, Array, Object, Proc, Symbol};
-use std::sync::Arc;
+use rutie::{util::is_method, AnyObject, Array, Boolean, Integer, Object, Proc, Symbol};
+use std::{convert::TryFrom, sync::Arc};
 
+/// A Ruby callable: a `Symbol`, a `Proc` or a `Method`.
 #[derive(Clone)]
-struct Callable(Arc<dyn Fn(&[AnyObject]) -> AnyObject>);
+pub(crate) struct Callable(Arc<dyn Fn(&[AnyObject]) -> AnyObject>);
 
 unsafe impl Send for Callable {}
 unsafe impl Sync for Callable {}
 
+impl TryFrom<&AnyObject> for Callable {
+    type Error = ();
+
+    fn try_from(function: &AnyObject) -> Result<Self, Self::Error> {
+        Ok(Callable(
+            if let Ok(symbol) = function.try_convert_to::<Symbol>() {
+                Arc::new(move |arguments| symbol.to_proc().call(arguments))
+            } else if let Ok(proc) = function.try_convert_to::<Proc>() {
+                Arc::new(move |arguments| proc.call(arguments))
+            } else if is_method(*function.as_ref()) {
+                let function = function.clone();
+
+                Arc::new(move |arguments| unsafe { function.send("call", arguments) })
+            } else {
+                return Err(());
+            },
+        ))
+    }
+}
+
+impl Callable {
+    pub(crate) fn call(&self, arguments: &[AnyObject]) -> AnyObject {
+        (self.0)(arguments)
+    }
+}
+
+/// Creates a host function that calls a Ruby callable.
+pub(crate) fn host_function(
+    store: &wasmer::Store,
+    function: Callable,
+    function_type: wasmer::FunctionType,
+) -> wasmer::Function {
+    #[derive(wasmer::WasmerEnv, Clone)]
+    struct Environment {
+        ruby_callable: Callable,
+        result_types: Vec<wasmer::Type>,
+    }
+
+    let environment = Environment {
+        ruby_callable: function,
+        result_types: function_type.results().to_vec(),
+    };
+
+    wasmer::Function::new_with_env(
+        store,
+        function_type,
+        environment,
+        |environment,
+         arguments: &[wasmer::Value]|
+         -> Result<Vec<wasmer::Value>, wasmer::RuntimeError> {
+            let arguments = arguments.iter().map(to_ruby_object).collect::<Vec<_>>();
+
+            let results = environment.ruby_callable.call(&arguments);
+
+            let result_types = &environment.result_types;
+            let has_result_types = !result_types.is_empty();
+
+            Ok(if let Ok(results) = results.try_convert_to::<Array>() {
+                results
+                    .into_iter()
+                    .zip(result_types)
+                    .map(|(value, ty)| to_wasm_value((&value, *ty)))
+                    .collect::<RubyResult<_>>()
+                    .map_err(|error| wasmer::RuntimeError::new(error.to_string()))?
+            } else if !results.is_nil() && has_result_types {
+                vec![to_wasm_value((&results, result_types[0]))
+                    .map_err(|error| wasmer::RuntimeError::new(error.to_string()))?]
+            } else {
+                Vec::new()
+            })
+        },
+    )
+}
+
 #[rubyclass(module = "Wasmer")]
 pub struct Function {
     inner: wasmer::Function,
@@ -36,72 +112,31 @@ impl Function {
         function: &AnyObject,
         function_type: &FunctionType,
     ) -> RubyResult<AnyObject> {
-        let function = Callable(if let Ok(symbol) = function.try_convert_to::<Symbol>() {
-            Arc::new(move |arguments| symbol.to_proc().call(arguments))
-        } else if let Ok(proc) = function.try_convert_to::<Proc>() {
-            Arc::new(move |arguments| proc.call(arguments))
-        } else if is_method(*function.as_ref()) {
-            let function = function.clone();
-
-            Arc::new(move |arguments| unsafe { function.send("call", arguments) })
-        } else {
-            return Err(to_ruby_err::<TypeError, _>(
+        let function = Callable::try_from(function).map_err(|_| {
+            to_ruby_err::<TypeError, _>(
                 "Argument #1 of `Function.new` must be either a `Symbol`, a `Proc`, or a `Method`",
-            ));
-        });
-
-        let function_type: wasmer::FunctionType = function_type.into();
-
-        #[derive(wasmer::WasmerEnv, Clone)]
-        struct Environment {
-            ruby_callable: Callable,
-            result_types: Vec<wasmer::Type>,
-        }
-
-        let environment = Environment {
-            ruby_callable: function,
-            result_types: function_type.results().to_vec(),
-        };
-
-        let host_function = wasmer::Function::new_with_env(
-            store.inner(),
-            function_type,
-            environment,
-            |environment,
-             arguments: &[wasmer::Value]|
-             -> Result<Vec<wasmer::Value>, wasmer::RuntimeError> {
-                let arguments = arguments.iter().map(to_ruby_object).collect::<Vec<_>>();
-
-                let ruby_callable = &environment.ruby_callable.0;
-                let results = ruby_callable(&arguments);
-
-                let result_types = &environment.result_types;
-                let has_result_types = !result_types.is_empty();
-
-                Ok(if let Ok(results) = results.try_convert_to::<Array>() {
-                    results
-                        .into_iter()
-                        .zip(result_types)
-                        .map(|(value, ty)| to_wasm_value((&value, *ty)))
-                        .collect::<RubyResult<_>>()
-                        .map_err(|error| wasmer::RuntimeError::new(error.to_string()))?
-                } else if !results.is_nil() && has_result_types {
-                    vec![to_wasm_value((&results, result_types[0]))
-                        .map_err(|error| wasmer::RuntimeError::new(error.to_string()))?]
-                } else {
-                    Vec::new()
-                })
-            },
-        );
+            )
+        })?;
 
         Ok(Function::ruby_new(Function {
-            inner: host_function,
+            inner: host_function(store.inner(), function, function_type.into()),
         }))
     }
 
     pub fn r#type(&self) -> RubyResult<AnyObject> {
         Ok(FunctionType::ruby_new(self.inner().ty().into()))
     }
+
+    pub fn eq(&self, other: &AnyObject) -> RubyResult<Boolean> {
+        Ok(Boolean::new(match other.try_convert_to::<RubyFunction>() {
+            Ok(other) => self.inner() == other.upcast().inner(),
+            Err(_) => false,
+        }))
+    }
+
+    pub fn hash(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(vm_address(self.inner())))
+    }
 }
 
 pub(crate) mod ruby_function_extra {
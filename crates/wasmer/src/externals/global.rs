@@ -1,11 +1,12 @@
 use crate::{
     error::{to_ruby_err, RuntimeError},
+    externals::vm_address,
     prelude::*,
     store::Store,
     types::GlobalType,
     values::{to_ruby_object, to_wasm_value, Value},
 };
-use rutie::{AnyObject, Boolean, NilClass};
+use rutie::{AnyObject, Boolean, Integer, NilClass, Object};
 
 #[rubyclass(module = "Wasmer")]
 pub struct Global {
@@ -59,4 +60,15 @@ impl Global {
     pub fn r#type(&self) -> RubyResult<AnyObject> {
         Ok(GlobalType::ruby_new(self.inner().ty().into()))
     }
+
+    pub fn eq(&self, other: &AnyObject) -> RubyResult<Boolean> {
+        Ok(Boolean::new(match other.try_convert_to::<RubyGlobal>() {
+            Ok(other) => self.inner().same(other.upcast().inner()),
+            Err(_) => false,
+        }))
+    }
+
+    pub fn hash(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(vm_address(self.inner())))
+    }
 }
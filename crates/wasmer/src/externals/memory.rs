@@ -1,11 +1,12 @@
 use crate::{
     error::{to_ruby_err, ArgumentError, RuntimeError},
+    externals::vm_address,
     memory::views::{Int16Array, Int32Array, Int8Array, Uint16Array, Uint32Array, Uint8Array},
     prelude::*,
     store::Store,
     types::MemoryType,
 };
-use rutie::{AnyObject, Fixnum, Integer, Object};
+use rutie::{AnyObject, Boolean, Fixnum, Integer, Object};
 use std::convert::{TryFrom, TryInto};
 
 #[rubyclass(module = "Wasmer")]
@@ -114,4 +115,15 @@ impl Memory {
             unwrap_offset(offset)?,
         )))
     }
+
+    pub fn eq(&self, other: &AnyObject) -> RubyResult<Boolean> {
+        Ok(Boolean::new(match other.try_convert_to::<RubyMemory>() {
+            Ok(other) => self.inner().same(other.upcast().inner()),
+            Err(_) => false,
+        }))
+    }
+
+    pub fn hash(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(vm_address(self.inner())))
+    }
 }
@@ -7,3 +7,50 @@ pub use function::Function;
 pub use global::Global;
 pub use memory::Memory;
 pub use table::Table;
+
+use crate::prelude::*;
+use rutie::{AnyObject, Object};
+use std::sync::Arc;
+
+/// Wraps a WebAssembly extern into a new Ruby object.
+pub(crate) fn to_ruby_object(r#extern: &wasmer::Extern) -> AnyObject {
+    match r#extern {
+        wasmer::Extern::Function(function) => {
+            Function::ruby_new(Function::raw_new(function.clone()))
+        }
+        wasmer::Extern::Memory(memory) => Memory::ruby_new(Memory::raw_new(memory.clone())),
+        wasmer::Extern::Global(global) => Global::ruby_new(Global::raw_new(global.clone())),
+        wasmer::Extern::Table(table) => Table::ruby_new(Table::raw_new(table.clone())),
+    }
+}
+
+/// Unwraps a Ruby `Function`, `Memory`, `Global` or `Table` into a
+/// WebAssembly extern.
+pub(crate) fn try_to_extern(object: &AnyObject) -> Option<wasmer::Extern> {
+    if let Ok(function) = object.try_convert_to::<function::RubyFunction>() {
+        Some(function.upcast().inner().clone().into())
+    } else if let Ok(memory) = object.try_convert_to::<memory::RubyMemory>() {
+        Some(memory.upcast().inner().clone().into())
+    } else if let Ok(global) = object.try_convert_to::<global::RubyGlobal>() {
+        Some(global.upcast().inner().clone().into())
+    } else if let Ok(table) = object.try_convert_to::<table::RubyTable>() {
+        Some(table.upcast().inner().clone().into())
+    } else {
+        None
+    }
+}
+
+/// Returns the address of the VM object backing an extern. It is used
+/// to implement `hash`: two externs that are equal have the same
+/// address.
+pub(crate) fn vm_address<'a, E>(r#extern: &E) -> i64
+where
+    E: wasmer::Exportable<'a>,
+{
+    (match r#extern.to_export() {
+        wasmer::Export::Function(function) => function.vm_function.address as *const u8,
+        wasmer::Export::Memory(memory) => Arc::as_ptr(&memory.from) as *const u8,
+        wasmer::Export::Global(global) => Arc::as_ptr(&global.from) as *const u8,
+        wasmer::Export::Table(table) => Arc::as_ptr(&table.from) as *const u8,
+    }) as i64
+}
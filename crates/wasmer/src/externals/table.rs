@@ -1,11 +1,12 @@
 use crate::{
     error::{to_ruby_err, RuntimeError},
+    externals::vm_address,
     prelude::*,
     store::Store,
     types::TableType,
     values::Value,
 };
-use rutie::AnyObject;
+use rutie::{AnyObject, Boolean, Integer, Object};
 
 #[rubyclass(module = "Wasmer")]
 pub struct Table {
@@ -38,4 +39,15 @@ impl Table {
             .map_err(to_ruby_err::<RuntimeError, _>)?,
         )))
     }
+
+    pub fn eq(&self, other: &AnyObject) -> RubyResult<Boolean> {
+        Ok(Boolean::new(match other.try_convert_to::<RubyTable>() {
+            Ok(other) => self.inner().same(other.upcast().inner()),
+            Err(_) => false,
+        }))
+    }
+
+    pub fn hash(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(vm_address(self.inner())))
+    }
 }
@@ -0,0 +1,107 @@
+use crate::{keywords::Keywords, prelude::*};
+use rutie::Boolean;
+
+#[rubyclass(module = "Wasmer")]
+pub struct Features {
+    inner: wasmer::Features,
+}
+
+impl Features {
+    pub(crate) fn inner(&self) -> &wasmer::Features {
+        &self.inner
+    }
+}
+
+#[rubymethods]
+impl Features {
+    pub fn simd(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().simd))
+    }
+
+    pub fn threads(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().threads))
+    }
+
+    pub fn reference_types(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().reference_types))
+    }
+
+    pub fn bulk_memory(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().bulk_memory))
+    }
+
+    pub fn multi_value(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().multi_value))
+    }
+
+    pub fn memory64(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().memory64))
+    }
+
+    pub fn module_linking(&self) -> RubyResult<Boolean> {
+        Ok(Boolean::new(self.inner().module_linking))
+    }
+}
+
+impl From<wasmer::Features> for Features {
+    fn from(inner: wasmer::Features) -> Self {
+        Self { inner }
+    }
+}
+
+pub(crate) mod ruby_features_extra {
+    use super::Features;
+    use crate::{error::unwrap_or_raise, keywords::Keywords};
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject,
+    };
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn new(argc: Argc, argv: *const AnyObject, _class: AnyObject) -> AnyObject {
+        unwrap_or_raise(|| {
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(argc, argv_pointer, str_to_cstring(":").as_ptr(), &keywords)
+            };
+
+            let keywords = Keywords::new(
+                AnyObject::from(keywords),
+                &[
+                    "simd",
+                    "threads",
+                    "reference_types",
+                    "bulk_memory",
+                    "multi_value",
+                    "memory64",
+                    "module_linking",
+                ],
+            )?;
+
+            Ok(Features::ruby_new(Features {
+                inner: super::from_keywords(&keywords)?,
+            }))
+        })
+    }
+}
+
+fn from_keywords(keywords: &Keywords) -> RubyResult<wasmer::Features> {
+    let defaults = wasmer::Features::default();
+    let mut features = wasmer::Features::default();
+
+    features
+        .simd(keywords.get_bool("simd", defaults.simd)?)
+        .threads(keywords.get_bool("threads", defaults.threads)?)
+        .reference_types(keywords.get_bool("reference_types", defaults.reference_types)?)
+        .bulk_memory(keywords.get_bool("bulk_memory", defaults.bulk_memory)?)
+        .multi_value(keywords.get_bool("multi_value", defaults.multi_value)?)
+        .memory64(keywords.get_bool("memory64", defaults.memory64)?)
+        .module_linking(keywords.get_bool("module_linking", defaults.module_linking)?);
+
+    Ok(features)
+}
@@ -1,22 +1,219 @@
 use crate::{
-    error::{to_ruby_err, unwrap_or_raise, TypeError},
-    externals::{function::RubyFunction, global::RubyGlobal, memory::RubyMemory, table::RubyTable},
+    error::{protect, to_ruby_err, NameError, RuntimeError, TypeError},
+    exports::RubyExports,
+    externals::{
+        function::{host_function, Callable},
+        to_ruby_object, try_to_extern,
+    },
+    instance::to_link_err,
+    module::Module,
     prelude::*,
+    types::extern_type_to_ruby_any_object,
+    values::{to_ruby_object as value_to_ruby_object, zero_value},
 };
-use rutie::{AnyObject, Boolean, Hash, NilClass, Object, RString, Symbol};
+use rutie::{
+    AnyException, AnyObject, Array, Boolean, Hash, NilClass, Object, Proc, RString, Symbol, VM,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+use wasmer::Exportable;
 
-#[rubyclass(module = "Wasmer")]
+#[rubyclass(module = "Wasmer", mark)]
 pub struct ImportObject {
-    inner: wasmer::ImportObject,
+    namespaces: BTreeMap<String, wasmer::Exports>,
+    /// The block given to `ImportObject.new`, called for the imports
+    /// that aren't registered.
+    resolver: Option<AnyObject>,
+    /// The calls to the stub functions, recorded with the `:log`
+    /// mode of `ImportObject.stubs_for`.
+    calls: Option<Arc<Mutex<Vec<StubCall>>>>,
+}
+
+/// A call to a stub function: its namespace, its name and its
+/// arguments.
+type StubCall = (String, String, Vec<wasmer::Value>);
+
+/// What the stubs generated by `ImportObject.stubs_for` do when they
+/// are called.
+#[derive(Clone, Copy, PartialEq)]
+enum StubMode {
+    /// Trap.
+    Trap,
+    /// Return zeros.
+    Zero,
+    /// Record the call and return zeros.
+    Log,
+}
+
+impl TryFrom<&Symbol> for StubMode {
+    type Error = &'static str;
+
+    fn try_from(value: &Symbol) -> Result<Self, Self::Error> {
+        Ok(match value.to_str() {
+            "trap" => StubMode::Trap,
+            "zero" => StubMode::Zero,
+            "log" => StubMode::Log,
+            _ => return Err("The stub mode must be `:trap`, `:zero` or `:log`"),
+        })
+    }
 }
 
 impl ImportObject {
     pub(crate) fn raw_new(inner: wasmer::ImportObject) -> Self {
-        Self { inner }
+        let mut namespaces = BTreeMap::<_, wasmer::Exports>::new();
+
+        for (namespace_name, name, r#extern) in inner.externs_vec() {
+            namespaces
+                .entry(namespace_name)
+                .or_default()
+                .insert(name, r#extern);
+        }
+
+        Self {
+            namespaces,
+            resolver: None,
+            calls: None,
+        }
+    }
+
+    pub(crate) fn from_namespaces(namespaces: BTreeMap<String, wasmer::Exports>) -> Self {
+        Self {
+            namespaces,
+            resolver: None,
+            calls: None,
+        }
+    }
+
+    /// Adds the externs of `namespace` to the namespace named
+    /// `namespace_name`, instead of replacing it. Raises a `NameError`
+    /// if one of them is already registered.
+    pub(crate) fn extend_namespace(
+        &mut self,
+        namespace_name: &str,
+        namespace: wasmer::Exports,
+    ) -> RubyResult<()> {
+        let existing_namespace = self
+            .namespaces
+            .entry(namespace_name.to_string())
+            .or_default();
+
+        if let Some((name, _)) = namespace
+            .iter()
+            .find(|(name, _)| existing_namespace.contains(name.as_str()))
+        {
+            return Err(to_ruby_err::<NameError, _>(format!(
+                "The import `{}` is already registered in the `{}` namespace",
+                name, namespace_name
+            )));
+        }
+
+        for (name, r#extern) in namespace.iter() {
+            existing_namespace.insert(name.clone(), r#extern.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Generates stubs for all the imports of `module`.
+    fn stubs_for(module: &wasmer::Module, mode: StubMode) -> RubyResult<Self> {
+        let store = module.store();
+        let calls = if mode == StubMode::Log {
+            Some(Arc::new(Mutex::new(Vec::new())))
+        } else {
+            None
+        };
+        let mut namespaces = BTreeMap::<_, wasmer::Exports>::new();
+
+        for import in module.imports() {
+            let namespace_name = import.module().to_string();
+            let name = import.name().to_string();
+
+            let r#extern: wasmer::Extern = match import.ty() {
+                wasmer::ExternType::Function(function_type) => {
+                    let calls = calls.clone();
+                    let result_types = function_type.results().to_vec();
+                    let (namespace_name, name) = (namespace_name.clone(), name.clone());
+
+                    wasmer::Function::new(store, function_type, move |arguments| {
+                        if mode == StubMode::Trap {
+                            return Err(wasmer::RuntimeError::new(format!(
+                                "The import `{}`.`{}` is a stub",
+                                namespace_name, name
+                            )));
+                        }
+
+                        if let Some(calls) = &calls {
+                            calls.lock().unwrap().push((
+                                namespace_name.clone(),
+                                name.clone(),
+                                arguments.to_vec(),
+                            ));
+                        }
+
+                        Ok(result_types.iter().map(|ty| zero_value(*ty)).collect())
+                    })
+                    .into()
+                }
+                wasmer::ExternType::Memory(memory_type) => wasmer::Memory::new(store, *memory_type)
+                    .map_err(to_ruby_err::<RuntimeError, _>)?
+                    .into(),
+                wasmer::ExternType::Global(global_type) => {
+                    let value = zero_value(global_type.ty);
+
+                    match global_type.mutability {
+                        wasmer::Mutability::Const => wasmer::Global::new(store, value),
+                        wasmer::Mutability::Var => wasmer::Global::new_mut(store, value),
+                    }
+                    .into()
+                }
+                wasmer::ExternType::Table(table_type) => {
+                    wasmer::Table::new(store, *table_type, zero_value(table_type.ty))
+                        .map_err(to_ruby_err::<RuntimeError, _>)?
+                        .into()
+                }
+            };
+
+            namespaces
+                .entry(namespace_name)
+                .or_default()
+                .insert(name, r#extern);
+        }
+
+        Ok(Self {
+            namespaces,
+            resolver: None,
+            calls,
+        })
+    }
+
+    /// Builds the resolver to instantiate `module` with.
+    pub(crate) fn to_resolver(&self, module: &wasmer::Module) -> ImportResolver {
+        let mut import_object = wasmer::ImportObject::new();
+
+        for (namespace_name, namespace) in self.namespaces.iter() {
+            import_object.register(namespace_name.as_str(), namespace.clone());
+        }
+
+        ImportResolver {
+            import_object,
+            resolver: self.resolver.as_ref().map(|resolver| {
+                (
+                    resolver.clone(),
+                    module.imports().map(|import| import.ty().clone()).collect(),
+                )
+            }),
+            error: RefCell::new(None),
+        }
     }
+}
 
-    pub(crate) fn inner(&self) -> &wasmer::ImportObject {
-        &self.inner
+impl GcMark for ImportObject {
+    fn gc_mark(&self) {
+        self.resolver.gc_mark();
     }
 }
 
@@ -24,55 +221,399 @@ impl ImportObject {
 impl ImportObject {
     pub fn new() -> RubyResult<AnyObject> {
         Ok(ImportObject::ruby_new(ImportObject {
-            inner: Default::default(),
+            namespaces: BTreeMap::new(),
+            resolver: if VM::is_block_given() {
+                Some(VM::block_proc().to_any_object())
+            } else {
+                None
+            },
+            calls: None,
         }))
     }
 
-    pub fn contains_namespace(&self, namespace_name: &RString) -> RubyResult<Boolean> {
+    pub fn contains_namespace(&self, namespace_name: &AnyObject) -> RubyResult<Boolean> {
         Ok(Boolean::new(
-            self.inner().contains_namespace(namespace_name.to_str()),
+            self.namespaces
+                .contains_key(&to_import_name(namespace_name)?),
         ))
     }
 
-    pub fn register(&mut self, namespace_name: &RString, namespace: &Hash) -> RubyResult<NilClass> {
-        let mut wasmer_namespace = wasmer::Exports::new();
+    pub fn register(
+        &mut self,
+        namespace_name: &AnyObject,
+        namespace: &AnyObject,
+    ) -> RubyResult<NilClass> {
+        let namespace_name = to_import_name(namespace_name)?;
+        let namespace = to_registered_namespace(&namespace_name, namespace)?;
 
-        namespace.each(|key, value| {
-            unwrap_or_raise(|| {
-                let name = if let Ok(name) = key.try_convert_to::<RString>() {
-                    name.to_string()
-                } else if let Ok(name) = key.try_convert_to::<Symbol>() {
-                    name.to_string()
-                } else {
-                    return Err(to_ruby_err::<TypeError, _>(format!(
-                        "`ImportObject` cannot register the name because it has an invalid type `{:?}` (expects `String` or `Symbol`)",
-                        key.ty()
-                    )));
+        self.namespaces.insert(namespace_name, namespace);
+
+        Ok(NilClass::new())
+    }
+
+    pub fn unregister(&mut self, namespace_name: &AnyObject) -> RubyResult<AnyObject> {
+        Ok(self
+            .namespaces
+            .remove(&to_import_name(namespace_name)?)
+            .map_or_else(
+                || NilClass::new().to_any_object(),
+                |namespace| namespace_to_hash(&namespace).to_any_object(),
+            ))
+    }
+
+    pub fn namespaces(&self) -> RubyResult<Array> {
+        Ok(self
+            .namespaces
+            .keys()
+            .map(|namespace_name| RString::new_utf8(namespace_name).to_any_object())
+            .collect())
+    }
+
+    pub fn get_namespace(&self, namespace_name: &AnyObject) -> RubyResult<AnyObject> {
+        Ok(self
+            .namespaces
+            .get(&to_import_name(namespace_name)?)
+            .map_or_else(
+                || NilClass::new().to_any_object(),
+                |namespace| namespace_to_hash(namespace).to_any_object(),
+            ))
+    }
+
+    pub fn get(&self, namespace_name: &AnyObject, name: &AnyObject) -> RubyResult<AnyObject> {
+        let name = to_import_name(name)?;
+
+        Ok(self
+            .namespaces
+            .get(&to_import_name(namespace_name)?)
+            .and_then(|namespace| namespace.get_extern(&name))
+            .map_or_else(|| NilClass::new().to_any_object(), to_ruby_object))
+    }
+
+    pub fn merge(&self, other: &ImportObject) -> RubyResult<AnyObject> {
+        let mut namespaces = self.namespaces.clone();
+
+        for (namespace_name, other_namespace) in other.namespaces.iter() {
+            let namespace = namespaces.entry(namespace_name.clone()).or_default();
+
+            for (name, r#extern) in other_namespace.iter() {
+                namespace.insert(name.clone(), r#extern.clone());
+            }
+        }
+
+        Ok(ImportObject::ruby_new(ImportObject {
+            namespaces,
+            resolver: other.resolver.clone().or_else(|| self.resolver.clone()),
+            calls: other.calls.clone().or_else(|| self.calls.clone()),
+        }))
+    }
+
+    pub fn to_h(&self) -> RubyResult<Hash> {
+        let mut hash = Hash::new();
+
+        for (namespace_name, namespace) in self.namespaces.iter() {
+            hash.store(
+                RString::new_utf8(namespace_name),
+                namespace_to_hash(namespace),
+            );
+        }
+
+        Ok(hash)
+    }
+
+    pub fn calls(&self) -> RubyResult<Array> {
+        let calls = match &self.calls {
+            Some(calls) => calls.lock().unwrap().clone(),
+            None => Vec::new(),
+        };
+
+        Ok(calls
+            .iter()
+            .map(|(namespace_name, name, arguments)| {
+                let mut call = Array::with_capacity(3);
+                call.push(RString::new_utf8(namespace_name));
+                call.push(RString::new_utf8(name));
+                call.push(
+                    arguments
+                        .iter()
+                        .map(|argument| match argument {
+                            wasmer::Value::I32(_)
+                            | wasmer::Value::I64(_)
+                            | wasmer::Value::F32(_)
+                            | wasmer::Value::F64(_) => value_to_ruby_object(argument),
+                            _ => NilClass::new().to_any_object(),
+                        })
+                        .collect::<Array>(),
+                );
+
+                call.to_any_object()
+            })
+            .collect())
+    }
+
+    pub fn validate_for(&self, module: &Module) -> RubyResult<Array> {
+        Ok(module
+            .inner()
+            .imports()
+            .filter_map(|import| {
+                let expected = import.ty().clone();
+                let import_error = match self
+                    .namespaces
+                    .get(import.module())
+                    .and_then(|namespace| namespace.get_extern(import.name()))
+                {
+                    None => wasmer_engine::ImportError::UnknownImport(expected),
+                    Some(r#extern) if !r#extern.ty().is_compatible_with(&expected) => {
+                        wasmer_engine::ImportError::IncompatibleType(expected, r#extern.ty())
+                    }
+                    Some(_) => return None,
                 };
 
-                if let Ok(function) = value.try_convert_to::<RubyFunction>() {
-                    wasmer_namespace.insert(name, function.upcast().inner().clone());
-                } else if let Ok(memory) = value.try_convert_to::<RubyMemory>() {
-                    wasmer_namespace.insert(name, memory.upcast().inner().clone());
-                } else if let Ok(global) = value.try_convert_to::<RubyGlobal>() {
-                    wasmer_namespace.insert(name, global.upcast().inner().clone());
-                } else if let Ok(table) = value.try_convert_to::<RubyTable>() {
-                    wasmer_namespace.insert(name, table.upcast().inner().clone());
-                } else {
+                Some(to_link_err(import.module(), import.name(), &import_error).to_any_object())
+            })
+            .collect())
+    }
+}
+
+/// Resolves the imports of a module with the registered namespaces
+/// first, and then with the resolver block, if any.
+pub(crate) struct ImportResolver {
+    import_object: wasmer::ImportObject,
+    /// The resolver block, and the types of the imports of the module.
+    resolver: Option<(AnyObject, Vec<wasmer::ExternType>)>,
+    /// The error raised by the resolver block, if any.
+    error: RefCell<Option<AnyException>>,
+}
+
+unsafe impl Send for ImportResolver {}
+unsafe impl Sync for ImportResolver {}
+
+impl ImportResolver {
+    pub(crate) fn new(import_object: wasmer::ImportObject) -> Self {
+        Self {
+            import_object,
+            resolver: None,
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Returns the error raised while resolving the imports, if any.
+    /// It takes precedence over the link error that it causes.
+    pub(crate) fn take_error(&self) -> Option<AnyException> {
+        self.error.borrow_mut().take()
+    }
+}
+
+impl wasmer::Resolver for ImportResolver {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<wasmer::Export> {
+        if let Some(export) = self.import_object.get_export(module, field) {
+            return Some(export);
+        }
+
+        let (resolver, import_types) = self.resolver.as_ref()?;
+        let import_type = import_types.get(index as usize)?;
+
+        let resolver = resolver.try_convert_to::<Proc>().ok()?;
+        let arguments = [
+            RString::new_utf8(module).to_any_object(),
+            RString::new_utf8(field).to_any_object(),
+            extern_type_to_ruby_any_object(import_type),
+        ];
+
+        // The resolver block is called while Wasmer instantiates the
+        // module, so an exception must not unwind through it: it is
+        // caught, and raised once the instantiation has failed.
+        let result = match protect(|| resolver.call(&arguments)) {
+            Ok(result) => result,
+            Err(error) => {
+                self.error.borrow_mut().get_or_insert(error);
+
+                return None;
+            }
+        };
+
+        if result.is_nil() {
+            return None;
+        }
+
+        match try_to_extern(&result) {
+            Some(r#extern) => Some(r#extern.to_export()),
+            None => {
+                self.error.borrow_mut().get_or_insert_with(|| {
+                    to_ruby_err::<TypeError, _>(format!(
+                        "The import resolver must return a `Function`, a `Memory`, a `Global`, a `Table` or `nil` for `{}`.`{}`, not a `{:?}`",
+                        module,
+                        field,
+                        result.ty(),
+                    ))
+                });
+
+                None
+            }
+        }
+    }
+}
+
+/// Converts a namespace into a `Hash` of externs, indexed by name.
+fn namespace_to_hash(namespace: &wasmer::Exports) -> Hash {
+    let mut hash = Hash::new();
+
+    for (name, r#extern) in namespace.iter() {
+        hash.store(RString::new_utf8(name), to_ruby_object(r#extern));
+    }
+
+    hash
+}
+
+/// Converts a `Hash` of externs, or the `Exports` of an instance, into
+/// a namespace.
+pub(crate) fn to_registered_namespace(
+    namespace_name: &str,
+    namespace: &AnyObject,
+) -> RubyResult<wasmer::Exports> {
+    if let Ok(exports) = namespace.try_convert_to::<RubyExports>() {
+        Ok(exports.upcast().inner().clone())
+    } else if let Ok(namespace) = namespace.try_convert_to::<Hash>() {
+        to_namespace(namespace_name, &namespace, None)
+    } else {
+        Err(to_ruby_err::<TypeError, _>(format!(
+            "`ImportObject` cannot register the namespace `{}` because it has an invalid type `{:?}` (expects `Hash` or `Exports`)",
+            namespace_name,
+            namespace.ty()
+        )))
+    }
+}
+
+/// Converts a `Hash` of externs into a namespace.
+///
+/// When `module` is given, a `Symbol`, a `Proc` or a `Method` is also
+/// accepted: it is wrapped into a function, whose type is the type of
+/// the matching function import of `module`.
+pub(crate) fn to_namespace(
+    namespace_name: &str,
+    namespace: &Hash,
+    module: Option<&wasmer::Module>,
+) -> RubyResult<wasmer::Exports> {
+    let mut wasmer_namespace = wasmer::Exports::new();
+    let mut result: RubyResult<()> = Ok(());
+
+    namespace.each(|key, value| {
+        if result.is_err() {
+            return;
+        }
+
+        result = (|| {
+            let name = to_import_name(&key)?;
+
+            let r#extern = match (try_to_extern(&value), module) {
+                (Some(r#extern), _) => r#extern,
+                (None, Some(module)) if Callable::try_from(&value).is_ok() => {
+                    let function_type = module
+                        .imports()
+                        .find_map(|import| match import.ty() {
+                            wasmer::ExternType::Function(function_type)
+                                if import.module() == namespace_name && import.name() == name =>
+                            {
+                                Some(function_type.clone())
+                            }
+                            _ => None,
+                        })
+                        .ok_or_else(|| {
+                            to_ruby_err::<TypeError, _>(format!(
+                                "Cannot infer the type of the function `{}` in the `{}` namespace, because the module does not import it",
+                                name, namespace_name,
+                            ))
+                        })?;
+
+                    host_function(
+                        module.store(),
+                        Callable::try_from(&value).unwrap(),
+                        function_type,
+                    )
+                    .into()
+                }
+                _ => {
                     return Err(to_ruby_err::<TypeError, _>(format!(
                         "`ImportObject` cannot register the given type `{:?}` associated to `{:?}`",
                         value.ty(),
                         name,
-                    )));
+                    )))
                 }
+            };
 
-                Ok(())
-            });
-        });
+            wasmer_namespace.insert(name, r#extern);
 
-        self.inner
-            .register(namespace_name.to_str(), wasmer_namespace);
+            Ok(())
+        })();
+    });
 
-        Ok(NilClass::new())
+    result.map(|_| wasmer_namespace)
+}
+
+/// Reads an import name, given as a `String` or a `Symbol`.
+pub(crate) fn to_import_name(name: &AnyObject) -> RubyResult<String> {
+    if let Ok(name) = name.try_convert_to::<RString>() {
+        Ok(name.to_string())
+    } else if let Ok(name) = name.try_convert_to::<Symbol>() {
+        Ok(name.to_string())
+    } else {
+        Err(to_ruby_err::<TypeError, _>(format!(
+            "`ImportObject` cannot register the name because it has an invalid type `{:?}` (expects `String` or `Symbol`)",
+            name.ty()
+        )))
+    }
+}
+
+pub(crate) mod ruby_importobject_extra {
+    use super::{ImportObject, StubMode};
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, ArgumentError},
+        keywords::Keywords,
+        module::RubyModule,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object, Symbol,
+    };
+    use rutie_derive::UpcastRubyClass;
+    use std::convert::TryFrom;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn stubs_for(
+        argc: Argc,
+        argv: *const AnyObject,
+        _class: AnyObject,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let module = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("1:").as_ptr(),
+                    &module,
+                    &keywords,
+                )
+            };
+
+            let module = AnyObject::from(module).try_convert_to::<RubyModule>()?;
+            let keywords = Keywords::new(AnyObject::from(keywords), &["mode"])?;
+
+            let mode = match keywords.get("mode") {
+                Some(mode) => StubMode::try_from(&mode.try_convert_to::<Symbol>()?)
+                    .map_err(to_ruby_err::<ArgumentError, _>)?,
+                None => StubMode::Trap,
+            };
+
+            Ok(ImportObject::ruby_new(ImportObject::stubs_for(
+                module.upcast().inner(),
+                mode,
+            )?))
+        })
     }
 }
@@ -1,45 +1,313 @@
 use crate::{
-    error::{to_ruby_err, RuntimeError},
-    exports::Exports,
-    import_object::RubyImportObject,
-    module::Module,
+    error::{
+        to_ruby_err, to_ruby_err_with_attributes, InstantiationError, LinkError, StartError,
+        TypeError,
+    },
+    exports::{Exports, RubyExports},
+    import_object::{to_import_name, to_namespace, ImportResolver, RubyImportObject},
+    module::{Module, DEFERRED_START_EXPORT},
     prelude::*,
+    types::extern_type_to_ruby_any_object,
 };
-use rutie::{AnyObject, Object};
+use rutie::{AnyException, AnyObject, Hash, NilClass, Object, RString};
 
-#[rubyclass(module = "Wasmer")]
+#[rubyclass(module = "Wasmer", mark)]
 pub struct Instance {
-    _inner: wasmer::Instance,
+    inner: wasmer::Instance,
     exports: AnyObject,
+    /// The externs and the Ruby callables of an `imports` `Hash`. The
+    /// callables are only referenced by the host functions wrapping
+    /// them, so they are marked here as long as the instance lives.
+    imports: Vec<AnyObject>,
+    /// The start function, and the `_initialize` function of a
+    /// reactor, if their calls have been deferred and they haven't
+    /// been called yet.
+    start: Vec<wasmer::Function>,
 }
 
-#[rubymethods]
+/// Name of the function exported by reactors, e.g. WASI reactors,
+/// to be called once before any other export.
+const INITIALIZE_EXPORT: &str = "_initialize";
+
 impl Instance {
-    pub fn new(module: &Module, import_object: &AnyObject) -> RubyResult<AnyObject> {
-        let module = module.inner();
+    /// Instantiates `module`, where `imports` is either `nil`, an
+    /// `ImportObject`, or a `Hash` of namespaces. When `initialize` is
+    /// `true`, the `_initialize` function of a reactor is called like
+    /// the start function, and hidden from the exports.
+    pub(crate) fn raw_new(
+        module: &Module,
+        imports: &AnyObject,
+        call_start: bool,
+        initialize: bool,
+    ) -> RubyResult<Self> {
+        let mut import_values = Vec::new();
+        let resolver = if imports.is_nil() {
+            ImportResolver::new(wasmer::imports! {})
+        } else if let Ok(imports) = imports.try_convert_to::<Hash>() {
+            ImportResolver::new(hash_to_import_object(
+                module.inner(),
+                &imports,
+                &mut import_values,
+            )?)
+        } else {
+            imports
+                .try_convert_to::<RubyImportObject>()?
+                .upcast()
+                .to_resolver(module.inner())
+        };
+
+        let deferred_start_module = if call_start {
+            None
+        } else {
+            module.deferred_start_module()?
+        };
+        let module = deferred_start_module
+            .as_ref()
+            .unwrap_or_else(|| module.inner());
 
-        let instance = if import_object.is_nil() {
-            wasmer::Instance::new(&module, &wasmer::imports! {})
+        let instance = wasmer::Instance::new(&module, &resolver);
+
+        if let Some(error) = resolver.take_error() {
+            return Err(error);
+        }
+
+        let instance = instance.map_err(to_instantiation_err)?;
+
+        let initialize = if initialize {
+            instance
+                .exports
+                .get_function(INITIALIZE_EXPORT)
+                .ok()
+                .filter(|function| {
+                    let function_type = function.ty();
+
+                    function_type.params().is_empty() && function_type.results().is_empty()
+                })
+                .cloned()
         } else {
-            wasmer::Instance::new(
-                &module,
-                import_object
-                    .try_convert_to::<RubyImportObject>()?
-                    .upcast()
-                    .inner(),
-            )
+            None
         };
 
-        let instance = instance.map_err(to_ruby_err::<RuntimeError, _>)?;
-        let exports = Exports::ruby_new(Exports::new(instance.exports.clone()));
+        // Hide the deferred start function, and the `_initialize`
+        // function that is called here, from the exports.
+        let mut exports = wasmer::Exports::new();
+        let mut start = Vec::new();
 
-        Ok(Instance::ruby_new(Instance {
-            _inner: instance,
-            exports,
-        }))
+        for (name, r#extern) in instance.exports.iter() {
+            match r#extern {
+                wasmer::Extern::Function(function)
+                    if deferred_start_module.is_some() && name == DEFERRED_START_EXPORT =>
+                {
+                    start.push(function.clone());
+                }
+                wasmer::Extern::Function(_)
+                    if name == INITIALIZE_EXPORT && initialize.is_some() => {}
+                _ => exports.insert(name.clone(), r#extern.clone()),
+            }
+        }
+
+        if let Some(initialize) = initialize {
+            if call_start {
+                initialize.call(&[]).map_err(to_ruby_err::<StartError, _>)?;
+            } else {
+                start.push(initialize);
+            }
+        }
+
+        Ok(Instance {
+            inner: instance,
+            exports: Exports::ruby_new(Exports::new(exports)),
+            imports: import_values,
+            start,
+        })
     }
 
+    pub(crate) fn inner(&self) -> &wasmer::Instance {
+        &self.inner
+    }
+
+    /// Returns the `Exports` object of the instance.
+    pub(crate) fn exports_object(&self) -> &AnyObject {
+        &self.exports
+    }
+}
+
+#[rubymethods]
+impl Instance {
     pub fn exports(&self) -> RubyResult<AnyObject> {
         Ok(self.exports.clone())
     }
+
+    pub fn define_methods_on(&self, object: &AnyObject) -> RubyResult<AnyObject> {
+        self.exports
+            .try_convert_to::<RubyExports>()?
+            .upcast()
+            .define_methods_on(object)?;
+
+        Ok(object.clone())
+    }
+
+    pub fn start(&mut self) -> RubyResult<NilClass> {
+        for start in std::mem::take(&mut self.start) {
+            start.call(&[]).map_err(to_ruby_err::<StartError, _>)?;
+        }
+
+        Ok(NilClass::new())
+    }
+}
+
+impl GcMark for Instance {
+    fn gc_mark(&self) {
+        self.exports.gc_mark();
+
+        for value in self.imports.iter() {
+            value.gc_mark();
+        }
+    }
+}
+
+/// Converts a `Hash` of namespaces, e.g. `{ "env" => { "log" => proc
+/// } }`, into an import object. The functions are given either as
+/// `Function`s, or as Ruby callables whose types are inferred from
+/// the imports of `module`. The values of the namespaces are pushed
+/// to `values`.
+fn hash_to_import_object(
+    module: &wasmer::Module,
+    imports: &Hash,
+    values: &mut Vec<AnyObject>,
+) -> RubyResult<wasmer::ImportObject> {
+    let mut import_object = wasmer::ImportObject::new();
+    let mut result: RubyResult<()> = Ok(());
+
+    imports.each(|namespace_name, namespace| {
+        if result.is_err() {
+            return;
+        }
+
+        result = (|| {
+            let namespace_name = to_import_name(&namespace_name)?;
+            let namespace = namespace.try_convert_to::<Hash>().map_err(|_| {
+                to_ruby_err::<TypeError, _>(format!(
+                    "The namespace `{}` must be a `Hash`, not a `{:?}`",
+                    namespace_name,
+                    namespace.ty()
+                ))
+            })?;
+            namespace.each(|_, value| values.push(value));
+
+            let namespace = to_namespace(&namespace_name, &namespace, Some(module))?;
+
+            import_object.register(namespace_name, namespace);
+
+            Ok(())
+        })();
+    });
+
+    result.map(|_| import_object)
+}
+
+/// Converts an instantiation error into a `Wasmer::LinkError`, a
+/// `Wasmer::StartError`, or a `Wasmer::InstantiationError`.
+pub(crate) fn to_instantiation_err(error: wasmer::InstantiationError) -> AnyException {
+    match error {
+        wasmer::InstantiationError::Link(wasmer::LinkError::Import(module, name, import_error)) => {
+            to_link_err(&module, &name, &import_error)
+        }
+        wasmer::InstantiationError::Start(error) => to_ruby_err::<StartError, _>(error),
+        error => to_ruby_err::<InstantiationError, _>(error),
+    }
+}
+
+/// Converts an import error into a `Wasmer::LinkError`, with the
+/// `module`, `name`, `expected` and `provided` attributes.
+pub(crate) fn to_link_err(
+    module: &str,
+    name: &str,
+    import_error: &wasmer_engine::ImportError,
+) -> AnyException {
+    let (expected, provided) = match import_error {
+        wasmer_engine::ImportError::IncompatibleType(expected, provided) => (
+            extern_type_to_ruby_any_object(expected),
+            extern_type_to_ruby_any_object(provided),
+        ),
+        wasmer_engine::ImportError::UnknownImport(expected) => (
+            extern_type_to_ruby_any_object(expected),
+            NilClass::new().to_any_object(),
+        ),
+    };
+
+    to_ruby_err_with_attributes::<LinkError, _>(
+        format!(
+            "Error while importing `{}`.`{}`: {}",
+            module, name, import_error
+        ),
+        &[
+            ("module", RString::new_utf8(module).to_any_object()),
+            ("name", RString::new_utf8(name).to_any_object()),
+            ("expected", expected),
+            ("provided", provided),
+        ],
+    )
+}
+
+pub(crate) mod ruby_instance_extra {
+    use super::Instance;
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, ArgumentError},
+        keywords::Keywords,
+        module::RubyModule,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn new(argc: Argc, argv: *const AnyObject, _class: AnyObject) -> AnyObject {
+        unwrap_or_raise(|| {
+            let module = Value::from(0);
+            let import_object = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("11:").as_ptr(),
+                    &module,
+                    &import_object,
+                    &keywords,
+                )
+            };
+
+            let module = AnyObject::from(module).try_convert_to::<RubyModule>()?;
+            let import_object = AnyObject::from(import_object);
+            let keywords = Keywords::new(
+                AnyObject::from(keywords),
+                &["imports", "call_start", "initialize"],
+            )?;
+
+            let imports = match keywords.get("imports") {
+                Some(_) if !import_object.is_nil() => {
+                    return Err(to_ruby_err::<ArgumentError, _>(
+                        "`Instance.new` accepts either an import object or the `imports` keyword argument, not both",
+                    ))
+                }
+                Some(imports) => imports,
+                None => import_object,
+            };
+
+            Ok(Instance::ruby_new(Instance::raw_new(
+                module.upcast(),
+                &imports,
+                keywords.get_bool("call_start", true)?,
+                keywords.get_bool("initialize", false)?,
+            )?))
+        })
+    }
 }
@@ -0,0 +1,103 @@
+use crate::{
+    error::{to_ruby_err, ArgumentError, RuntimeError, SignatureError},
+    module_bytes::ModuleBytes,
+    prelude::*,
+};
+use ed25519_dalek::{PublicKey, Signature, Verifier, PUBLIC_KEY_LENGTH};
+use rutie::{AnyObject, Integer, RString};
+use std::convert::TryFrom;
+
+/// Name of the custom section holding the signatures of a module.
+pub(crate) const SIGNATURE_SECTION_NAME: &str = "signature";
+
+#[rubyclass(module = "Wasmer")]
+pub struct Keyring {
+    keys: Vec<PublicKey>,
+}
+
+impl Keyring {
+    /// Verifies that the module represented by `bytes` has been
+    /// signed by one of the keys of the keyring.
+    ///
+    /// The signatures are computed over the module bytes without the
+    /// signature custom sections.
+    pub(crate) fn verify(&self, bytes: &[u8]) -> RubyResult<()> {
+        let mut module_bytes = ModuleBytes::from_bytes(bytes)?;
+        let signatures = module_bytes.take_custom_sections(SIGNATURE_SECTION_NAME);
+
+        if signatures.is_empty() {
+            return Err(to_ruby_err::<SignatureError, _>(format!(
+                "The module is not signed (the `{}` custom section is missing)",
+                SIGNATURE_SECTION_NAME
+            )));
+        }
+
+        let message = module_bytes.encode();
+        let is_trusted = signatures
+            .iter()
+            .filter_map(|signature| Signature::try_from(signature.as_slice()).ok())
+            .any(|signature| {
+                self.keys
+                    .iter()
+                    .any(|key| key.verify(&message, &signature).is_ok())
+            });
+
+        if !is_trusted {
+            return Err(to_ruby_err::<SignatureError, _>(
+                "The module signature is invalid, or has not been made by a trusted key",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[rubymethods]
+impl Keyring {
+    pub fn new() -> RubyResult<AnyObject> {
+        Ok(Keyring::ruby_new(Keyring { keys: Vec::new() }))
+    }
+
+    pub fn add_key(&mut self, key: &RString) -> RubyResult<RubyKeyring> {
+        self.keys.push(
+            PublicKey::from_bytes(key.to_bytes_unchecked())
+                .map_err(to_ruby_err::<ArgumentError, _>)?,
+        );
+
+        Ok(_ruby_self)
+    }
+
+    pub fn add_key_file(&mut self, path: &RString) -> RubyResult<RubyKeyring> {
+        let content = std::fs::read(path.to_str()).map_err(to_ruby_err::<RuntimeError, _>)?;
+        let key = if content.len() == PUBLIC_KEY_LENGTH {
+            content
+        } else {
+            decode_hex(String::from_utf8_lossy(&content).trim()).ok_or_else(|| {
+                to_ruby_err::<ArgumentError, _>(format!(
+                    "The key file `{}` must contain a raw or an hexadecimal ed25519 public key",
+                    path.to_str()
+                ))
+            })?
+        };
+
+        self.keys
+            .push(PublicKey::from_bytes(&key).map_err(to_ruby_err::<ArgumentError, _>)?);
+
+        Ok(_ruby_self)
+    }
+
+    pub fn length(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(self.keys.len() as i64))
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,62 @@
+//! Functions to read keyword arguments correctly.
+
+use crate::error::{to_ruby_err, unwrap_or_raise, ArgumentError, RubyResult, TypeError};
+use rutie::{AnyObject, Boolean, Hash, Object, Symbol};
+
+/// Keyword arguments of a method, as collected by `rb_scan_args`
+/// with the `:` format.
+pub(crate) struct Keywords {
+    hash: Option<Hash>,
+}
+
+impl Keywords {
+    /// Reads the keyword arguments, and checks that only the
+    /// `accepted` keywords are present.
+    pub(crate) fn new(hash: AnyObject, accepted: &[&str]) -> RubyResult<Self> {
+        if hash.is_nil() {
+            return Ok(Self { hash: None });
+        }
+
+        let hash = hash.try_convert_to::<Hash>()?;
+
+        hash.each(|key, _value| {
+            unwrap_or_raise(|| {
+                let key = key.try_convert_to::<Symbol>().map_err(|_| {
+                    to_ruby_err::<TypeError, _>(format!(
+                        "Keyword has an invalid type `{:?}` (expects `Symbol`)",
+                        key.ty()
+                    ))
+                })?;
+
+                if !accepted.contains(&key.to_str()) {
+                    return Err(to_ruby_err::<ArgumentError, _>(format!(
+                        "unknown keyword: :{}",
+                        key.to_str()
+                    )));
+                }
+
+                Ok(())
+            });
+        });
+
+        Ok(Self { hash: Some(hash) })
+    }
+
+    /// Returns the value of a keyword, or `None` if the keyword is
+    /// absent or `nil`.
+    pub(crate) fn get(&self, name: &str) -> Option<AnyObject> {
+        self.hash
+            .as_ref()
+            .map(|hash| hash.at(&Symbol::new(name)))
+            .filter(|value| !value.is_nil())
+    }
+
+    /// Returns the value of a boolean keyword, or `default` if the
+    /// keyword is absent.
+    pub(crate) fn get_bool(&self, name: &str, default: bool) -> RubyResult<bool> {
+        match self.get(name) {
+            Some(value) => Ok(value.try_convert_to::<Boolean>()?.to_bool()),
+            None => Ok(default),
+        }
+    }
+}
@@ -17,15 +17,22 @@
 
 #[cfg(doc)]
 mod doc;
+mod emscripten;
 mod error;
 mod exports;
 mod externals;
+mod features;
 mod import_object;
 mod instance;
+mod keyring;
+mod keywords;
+mod linker;
 mod memory;
 mod module;
+mod module_bytes;
 mod prelude;
 mod store;
+mod target;
 mod types;
 mod values;
 mod wasi;
@@ -88,13 +95,35 @@ pub extern "C" fn init() {
 
     wasmer_module.const_set("VERSION", &RString::new_utf8(env!("CARGO_PKG_VERSION")));
 
+    error::define_wasmer_errors(&mut wasmer_module);
+
     ruby_define! {
         in wasmer_module
-            class (store::ruby_store) Store {
+            class (store::ruby_store, store::ruby_store_extra) Store {
                 def_self (new) "new";
+                def (features) "features";
+                def (target) "target";
             };
 
-            class (module::ruby_module) Module {
+            class (target::ruby_target) Target {
+                def_self (new) "new";
+                def_self (host) "host";
+                def (triple) "triple";
+                def (cpu_features) "cpu_features";
+            };
+
+            class (features::ruby_features, features::ruby_features_extra) Features {
+                def_self (new) "new";
+                def (simd) "simd?";
+                def (threads) "threads?";
+                def (reference_types) "reference_types?";
+                def (bulk_memory) "bulk_memory?";
+                def (multi_value) "multi_value?";
+                def (memory64) "memory64?";
+                def (module_linking) "module_linking?";
+            };
+
+            class (module::ruby_module, module::ruby_module_extra) Module {
                 def_self (validate) "validate";
                 def_self (new) "new";
                 def (set_name) "name=";
@@ -102,31 +131,88 @@ pub extern "C" fn init() {
                 def (exports) "exports";
                 def (imports) "imports";
                 def (custom_sections) "custom_sections";
+                def (custom_section_names) "custom_section_names";
+                def (functions) "functions";
+                def (start_function) "start_function";
+                def (memories) "memories";
+                def (globals) "globals";
+                def (tables) "tables";
                 def (serialize) "serialize";
                 def_self (deserialize) "deserialize";
             };
 
-            class (instance::ruby_instance) Instance {
+            class (module_bytes::ruby_modulebytes) ModuleBytes {
+                def_self (new) "new";
+                def (custom_section_names) "custom_section_names";
+                def (custom_sections) "custom_sections";
+                def (add_custom_section) "add_custom_section";
+                def (replace_custom_section) "replace_custom_section";
+                def (strip_custom_section) "strip_custom_section";
+                def (strip_debug_sections) "strip_debug_sections";
+                def (to_s) "to_s";
+            };
+
+            class (keyring::ruby_keyring) Keyring {
+                @const SIGNATURE_SECTION = RString::new_utf8(keyring::SIGNATURE_SECTION_NAME);
+                def_self (new) "new";
+                def (add_key) "add_key";
+                def (add_key_file) "add_key_file";
+                def (length) "length";
+            };
+
+            class (instance::ruby_instance, instance::ruby_instance_extra) Instance {
                 def_self (new) "new";
                 def (exports) "exports";
+                def (define_methods_on) "define_methods_on";
+                def (start) "start";
             };
 
             class (exports::ruby_exports, exports::ruby_exports_extra) Exports {
                 def (respond_to_missing) "respond_to_missing?";
                 def (method_missing) "method_missing";
                 def (length) "length";
+                def (get) "[]";
+                def (each) "each";
+                def (keys) "keys";
+                def (to_h) "to_h";
+                def (to_module) "to_module";
+                def (function) "function";
+                def (memory) "memory";
+                def (global) "global";
+                def (table) "table";
             };
 
-            class (import_object::ruby_importobject) ImportObject {
+            class (import_object::ruby_importobject, import_object::ruby_importobject_extra) ImportObject {
                 def_self (new) "new";
+                def_self (stubs_for) "stubs_for";
                 def (contains_namespace) "contains_namespace?";
                 def (register) "register";
+                def (unregister) "unregister";
+                def (namespaces) "namespaces";
+                def (get_namespace) "[]";
+                def (get) "get";
+                def (merge) "merge";
+                def (to_h) "to_h";
+                def (calls) "calls";
+                def (validate_for) "validate_for";
+            };
+
+            class (linker::ruby_linker) Linker {
+                def_self (new) "new";
+                def (define) "define";
+                def (register) "register";
+                def (modules) "modules";
+                def (instantiate) "instantiate";
+                def (get) "[]";
             };
 
             class (externals::function::ruby_function, externals::function::ruby_function_extra) Function {
                 def_self (new) "new";
                 def (call) "call";
                 def (r#type) "type";
+                def (eq) "==";
+                def (eq) "eql?";
+                def (hash) "hash";
             };
 
             class (externals::memory::ruby_memory) Memory {
@@ -141,6 +227,9 @@ pub extern "C" fn init() {
                 def (int16_view) "int16_view";
                 def (uint32_view) "uint32_view";
                 def (int32_view) "int32_view";
+                def (eq) "==";
+                def (eq) "eql?";
+                def (hash) "hash";
             };
 
             class (memory::views::ruby_uint8array) Uint8Array
@@ -209,10 +298,16 @@ pub extern "C" fn init() {
                 def (get_value) "value";
                 def (set_value) "value=";
                 def (r#type) "type";
+                def (eq) "==";
+                def (eq) "eql?";
+                def (hash) "hash";
             };
 
             class (externals::table::ruby_table) Table {
                 def_self (new) "new";
+                def (eq) "==";
+                def (eq) "eql?";
+                def (hash) "hash";
             };
 
             class (types) Type {
@@ -264,6 +359,12 @@ pub extern "C" fn init() {
                 def (r#type) "type";
             };
 
+            class (types::ruby_functioninfo) FunctionInfo {
+                def (index) "index";
+                def (name) "name";
+                def (r#type) "type";
+            };
+
             class (values::ruby_value) Value {
                 def_self (i32) "i32";
                 def_self (i64) "i64";
@@ -275,6 +376,15 @@ pub extern "C" fn init() {
             function (wat::wasm2wat) "wasm2wat";
     };
 
+    let mut wasmer_emscripten_module = wasmer_module.define_nested_module("Emscripten");
+
+    ruby_define! {
+        in wasmer_emscripten_module
+            function (emscripten::detect) "detect?";
+            function (emscripten::generate_import_object) "generate_import_object";
+            function (emscripten::ruby_emscripten_extra::run) "run";
+    };
+
     let mut wasmer_wasi_module = wasmer_module.define_nested_module("Wasi");
 
     ruby_define! {
@@ -285,7 +395,7 @@ pub extern "C" fn init() {
                 @const SNAPSHOT1 = Integer::new(3);
             };
 
-            class (wasi::ruby_statebuilder) StateBuilder {
+            class (wasi::ruby_statebuilder, wasi::ruby_statebuilder_extra) StateBuilder {
                 def_self (new) "new";
                 def (arguments) "arguments";
                 def (argument) "argument";
@@ -295,13 +405,38 @@ pub extern "C" fn init() {
                 def (preopen_directory) "preopen_directory";
                 def (map_directories) "map_directories";
                 def (map_directory) "map_directory";
+                def (capture_stdout) "capture_stdout";
+                def (capture_stderr) "capture_stderr";
+                def (stdin) "stdin";
+                def (mount) "mount";
+                def (clock) "clock";
+                def (random_seed) "random_seed";
+                def (trace) "trace";
                 def (finalize) "finalize";
             };
 
-            class (wasi::ruby_environment) Environment {
+            class (wasi::ruby_memfs, wasi::ruby_memfs_extra) MemFS {
+                def_self (new) "new";
+                def (write_file) "write_file";
+                def (read_file) "read_file";
+                def (mkdir) "mkdir";
+                def (list) "list";
+            };
+
+            class (wasi::ruby_environment, wasi::ruby_environment_extra) Environment {
                 def (generate_import_object) "generate_import_object";
+                def (run) "run";
+                def (arguments) "arguments";
+                def (set_arguments) "arguments=";
+                def (environment_variables) "environment_variables";
+                def (set_environment_variables) "environment_variables=";
+                def (file_descriptors) "file_descriptors";
+                def (preopened_directories) "preopened_directories";
+                def (read_file) "read_file";
+                def (stdout) "stdout";
+                def (stderr) "stderr";
             };
 
-            function (wasi::get_version) "get_version";
+            function (wasi::ruby_wasi_extra::get_version) "get_version";
     };
 }
@@ -0,0 +1,163 @@
+use crate::{
+    error::{to_ruby_err, ArgumentError, InstantiationError},
+    import_object::{to_import_name, to_registered_namespace, ImportObject},
+    instance::{Instance, RubyInstance},
+    module::RubyModule,
+    prelude::*,
+};
+use rutie::{AnyObject, Array, NilClass, Object, RString};
+use std::collections::{BTreeMap, HashMap};
+
+#[rubyclass(module = "Wasmer", mark)]
+pub struct Linker {
+    /// The modules, indexed by the namespace they provide.
+    modules: HashMap<String, AnyObject>,
+    /// The namespaces registered statically, that aren't provided by
+    /// a module.
+    namespaces: BTreeMap<String, wasmer::Exports>,
+    /// The instances of the modules, built once, on demand.
+    instances: HashMap<String, AnyObject>,
+}
+
+impl Linker {
+    /// Instantiates the module named `name`, after the modules it
+    /// imports from, and returns its instance. `path` holds the
+    /// modules being instantiated, to detect cycles.
+    fn instantiate_module(&mut self, name: &str, path: &mut Vec<String>) -> RubyResult<AnyObject> {
+        if let Some(instance) = self.instances.get(name) {
+            return Ok(instance.clone());
+        }
+
+        if path.iter().any(|module_name| module_name == name) {
+            path.push(name.to_string());
+
+            return Err(to_ruby_err::<InstantiationError, _>(format!(
+                "Cannot instantiate `{}`: the modules import each other ({})",
+                name,
+                path.join(" -> ")
+            )));
+        }
+
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| {
+                to_ruby_err::<ArgumentError, _>(format!("Module `{}` is not defined", name))
+            })?
+            .try_convert_to::<RubyModule>()?;
+
+        let mut dependencies = module
+            .upcast()
+            .inner()
+            .imports()
+            .map(|import| import.module().to_string())
+            .filter(|namespace_name| self.modules.contains_key(namespace_name))
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        dependencies.dedup();
+
+        path.push(name.to_string());
+
+        let mut namespaces = self.namespaces.clone();
+
+        for dependency in dependencies {
+            let instance = self.instantiate_module(&dependency, path)?;
+            let exports = instance
+                .try_convert_to::<RubyInstance>()?
+                .upcast()
+                .exports_object()
+                .clone();
+
+            namespaces.insert(
+                dependency.clone(),
+                to_registered_namespace(&dependency, &exports)?,
+            );
+        }
+
+        path.pop();
+
+        let import_object = ImportObject::ruby_new(ImportObject::from_namespaces(namespaces));
+        let instance = Instance::ruby_new(Instance::raw_new(
+            module.upcast(),
+            &import_object,
+            true,
+            false,
+        )?);
+
+        self.instances.insert(name.to_string(), instance.clone());
+
+        Ok(instance)
+    }
+}
+
+impl GcMark for Linker {
+    fn gc_mark(&self) {
+        for object in self.modules.values().chain(self.instances.values()) {
+            object.gc_mark();
+        }
+    }
+}
+
+#[rubymethods]
+impl Linker {
+    pub fn new() -> RubyResult<AnyObject> {
+        Ok(Linker::ruby_new(Linker {
+            modules: HashMap::new(),
+            namespaces: BTreeMap::new(),
+            instances: HashMap::new(),
+        }))
+    }
+
+    pub fn define(&mut self, name: &AnyObject, module: &AnyObject) -> RubyResult<RubyLinker> {
+        let name = to_import_name(name)?;
+        module.try_convert_to::<RubyModule>()?;
+
+        if self.modules.contains_key(&name) {
+            return Err(to_ruby_err::<ArgumentError, _>(format!(
+                "Module `{}` is already defined",
+                name
+            )));
+        }
+
+        self.modules.insert(name, module.clone());
+
+        Ok(_ruby_self)
+    }
+
+    pub fn register(
+        &mut self,
+        namespace_name: &AnyObject,
+        namespace: &AnyObject,
+    ) -> RubyResult<RubyLinker> {
+        let namespace_name = to_import_name(namespace_name)?;
+        let namespace = to_registered_namespace(&namespace_name, namespace)?;
+
+        self.namespaces.insert(namespace_name, namespace);
+
+        Ok(_ruby_self)
+    }
+
+    pub fn modules(&self) -> RubyResult<Array> {
+        let mut names = self.modules.keys().collect::<Vec<_>>();
+        names.sort();
+
+        Ok(names
+            .into_iter()
+            .map(|name| RString::new_utf8(name).to_any_object())
+            .collect())
+    }
+
+    pub fn instantiate(&mut self, name: &AnyObject) -> RubyResult<AnyObject> {
+        let name = to_import_name(name)?;
+
+        self.instantiate_module(&name, &mut Vec::new())
+    }
+
+    pub fn get(&self, name: &AnyObject) -> RubyResult<AnyObject> {
+        Ok(self
+            .instances
+            .get(&to_import_name(name)?)
+            .cloned()
+            .unwrap_or_else(|| NilClass::new().to_any_object()))
+    }
+}
@@ -1,22 +1,107 @@
 use crate::{
     error::{to_ruby_err, RuntimeError},
+    module_bytes::ModuleBytes,
     prelude::*,
     store::Store,
-    types::{ExportType, ImportType},
+    types::{ExportType, FunctionInfo, GlobalType, ImportType, MemoryType, TableType},
 };
-use rutie::{AnyObject, Array, Boolean, Encoding, NilClass, Object, RString};
-use std::convert::TryFrom;
+use rutie::{AnyObject, Array, Boolean, Encoding, Integer, NilClass, Object, RString};
+use std::{
+    cell::RefCell,
+    convert::{TryFrom, TryInto},
+};
+use wasmer_types::entity::EntityRef;
+
+/// Prefix of a serialized module. It is followed by the target
+/// triple, the CPU features of the target (comma-separated), each
+/// preceded by its length (as a little-endian `u32`), and finally the
+/// artifact. Modules serialized before this prefix existed are
+/// deserialized without checking their target.
+const SERIALIZED_MODULE_PREFIX: &[u8] = b"\0wasmer-ruby-module\0";
+
+/// Name of the export of the start function, when its call is
+/// deferred.
+pub(crate) const DEFERRED_START_EXPORT: &str = "__wasmer_ruby_start";
 
 #[rubyclass(module = "Wasmer")]
 pub struct Module {
     inner: wasmer::Module,
+    /// The bytes of the module, kept only if it has a start function,
+    /// until the module with a deferred start function is compiled.
+    bytes: RefCell<Option<Vec<u8>>>,
+    /// The module compiled with a deferred start function, compiled
+    /// once on the first instantiation that does not call the start
+    /// function.
+    deferred_start_module: RefCell<Option<wasmer::Module>>,
 }
 
 impl Module {
+    fn raw_new(inner: wasmer::Module, bytes: &[u8]) -> Self {
+        let bytes = if inner.info().start_function.is_some() {
+            Some(bytes.to_vec())
+        } else {
+            None
+        };
+
+        Self {
+            inner,
+            bytes: RefCell::new(bytes),
+            deferred_start_module: RefCell::new(None),
+        }
+    }
+
     pub(crate) fn inner(&self) -> &wasmer::Module {
         &self.inner
     }
 
+    /// Returns a variant of the module where the start function is
+    /// not called on instantiation, but exported as
+    /// `DEFERRED_START_EXPORT` instead. Returns `None` if the module
+    /// has no start function.
+    pub(crate) fn deferred_start_module(&self) -> RubyResult<Option<wasmer::Module>> {
+        if self.inner().info().start_function.is_none() {
+            return Ok(None);
+        }
+
+        if let Some(module) = self.deferred_start_module.borrow().as_ref() {
+            return Ok(Some(module.clone()));
+        }
+
+        if self
+            .inner()
+            .exports()
+            .any(|export| export.name() == DEFERRED_START_EXPORT)
+        {
+            return Err(to_ruby_err::<RuntimeError, _>(format!(
+                "The start function cannot be deferred, because the module already exports `{}`",
+                DEFERRED_START_EXPORT
+            )));
+        }
+
+        let mut module_bytes = match self.bytes.borrow().as_ref() {
+            Some(bytes) => ModuleBytes::from_bytes(bytes)?,
+            None => {
+                return Err(to_ruby_err::<RuntimeError, _>(
+                    "The start function of a deserialized module cannot be deferred",
+                ))
+            }
+        };
+        module_bytes.defer_start_function(DEFERRED_START_EXPORT)?;
+
+        let mut module = wasmer::Module::new(self.inner().store(), module_bytes.encode())
+            .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+        if let Some(name) = self.inner().name() {
+            module.set_name(name);
+        }
+
+        // The bytes are not needed anymore once the module is compiled.
+        *self.bytes.borrow_mut() = None;
+        *self.deferred_start_module.borrow_mut() = Some(module.clone());
+
+        Ok(Some(module))
+    }
+
     fn inner_mut(&mut self) -> &mut wasmer::Module {
         &mut self.inner
     }
@@ -24,14 +109,6 @@ impl Module {
 
 #[rubymethods]
 impl Module {
-    pub fn new(store: &Store, bytes: &RString) -> RubyResult<AnyObject> {
-        let module = wasmer::Module::new(store.inner(), bytes.to_bytes_unchecked());
-
-        Ok(Module::ruby_new(Module {
-            inner: module.map_err(to_ruby_err::<RuntimeError, _>)?,
-        }))
-    }
-
     pub fn validate(store: &Store, bytes: &AnyObject) -> RubyResult<Boolean> {
         Ok(Boolean::new(match bytes.try_convert_to::<RString>() {
             Ok(bytes) => {
@@ -51,6 +128,10 @@ impl Module {
     pub fn set_name(&mut self, name: &RString) -> RubyResult<NilClass> {
         self.inner_mut().set_name(name.to_str());
 
+        if let Some(module) = self.deferred_start_module.get_mut() {
+            module.set_name(name.to_str());
+        }
+
         Ok(NilClass::new())
     }
 
@@ -76,6 +157,73 @@ impl Module {
         Ok(array)
     }
 
+    pub fn functions(&self) -> RubyResult<Array> {
+        let info = self.inner().info();
+
+        Ok(info
+            .functions
+            .iter()
+            .filter(|(index, _)| !info.is_imported_function(*index))
+            .map(|(index, signature_index)| {
+                FunctionInfo::ruby_new(FunctionInfo {
+                    index: index.index() as u32,
+                    name: info.function_names.get(&index).cloned(),
+                    ty: info.signatures[*signature_index].clone(),
+                })
+            })
+            .collect())
+    }
+
+    pub fn start_function(&self) -> RubyResult<AnyObject> {
+        Ok(self.inner().info().start_function.map_or_else(
+            || NilClass::new().to_any_object(),
+            |index| Integer::new(index.index() as i64).to_any_object(),
+        ))
+    }
+
+    pub fn memories(&self) -> RubyResult<Array> {
+        let info = self.inner().info();
+
+        Ok(info
+            .memories
+            .iter()
+            .filter(|(index, _)| !info.is_imported_memory(*index))
+            .map(|(_, memory_type)| MemoryType::ruby_new(MemoryType::from(memory_type)))
+            .collect())
+    }
+
+    pub fn globals(&self) -> RubyResult<Array> {
+        let info = self.inner().info();
+
+        Ok(info
+            .globals
+            .iter()
+            .filter(|(index, _)| !info.is_imported_global(*index))
+            .map(|(_, global_type)| GlobalType::ruby_new(GlobalType::from(global_type)))
+            .collect())
+    }
+
+    pub fn tables(&self) -> RubyResult<Array> {
+        let info = self.inner().info();
+
+        Ok(info
+            .tables
+            .iter()
+            .filter(|(index, _)| !info.is_imported_table(*index))
+            .map(|(_, table_type)| TableType::ruby_new(TableType::from(table_type)))
+            .collect())
+    }
+
+    pub fn custom_section_names(&self) -> RubyResult<Array> {
+        Ok(self
+            .inner()
+            .info()
+            .custom_sections
+            .keys()
+            .map(|name| RString::new_utf8(name).to_any_object())
+            .collect())
+    }
+
     pub fn custom_sections(&self, name: &RString) -> RubyResult<Array> {
         Ok(self
             .inner()
@@ -87,20 +235,188 @@ impl Module {
     }
 
     pub fn serialize(&self) -> RubyResult<RString> {
-        Ok(RString::from_bytes(
-            self.inner()
-                .serialize()
-                .map_err(to_ruby_err::<RuntimeError, _>)?
-                .as_slice(),
-            &Encoding::us_ascii(),
-        ))
+        let target = self.inner().store().engine().target();
+        let triple = target.triple().to_string();
+        let cpu_features = cpu_feature_names(target).join(",");
+        let artifact = self
+            .inner()
+            .serialize()
+            .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+        let mut bytes = Vec::with_capacity(
+            SERIALIZED_MODULE_PREFIX.len() + 8 + triple.len() + cpu_features.len() + artifact.len(),
+        );
+        bytes.extend_from_slice(SERIALIZED_MODULE_PREFIX);
+
+        for field in &[triple, cpu_features] {
+            bytes.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(field.as_bytes());
+        }
+
+        bytes.extend_from_slice(&artifact);
+
+        Ok(RString::from_bytes(bytes.as_slice(), &Encoding::us_ascii()))
     }
 
     pub fn deserialize(store: &Store, bytes: &RString) -> RubyResult<AnyObject> {
-        let module =
-            unsafe { wasmer::Module::deserialize(store.inner(), bytes.to_bytes_unchecked()) }
+        let bytes = bytes.to_bytes_unchecked();
+
+        let artifact = match bytes.strip_prefix(SERIALIZED_MODULE_PREFIX) {
+            Some(bytes) => {
+                let (triple, cpu_features, artifact) =
+                    split_serialized_module(bytes).ok_or_else(|| {
+                        to_ruby_err::<RuntimeError, _>(
+                            "The given bytes are not a serialized module",
+                        )
+                    })?;
+
+                check_serialized_target(store.inner().engine().target(), triple, cpu_features)?;
+
+                artifact
+            }
+
+            // The module has been serialized before the target was
+            // recorded, it cannot be checked.
+            None => bytes,
+        };
+
+        let module = unsafe { wasmer::Module::deserialize(store.inner(), artifact) }
+            .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+        Ok(Module::ruby_new(Module {
+            inner: module,
+            bytes: RefCell::new(None),
+            deferred_start_module: RefCell::new(None),
+        }))
+    }
+}
+
+pub(crate) mod ruby_module_extra {
+    use super::Module;
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, RuntimeError},
+        keyring::RubyKeyring,
+        keywords::Keywords,
+        store::RubyStore,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object, RString,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn new(argc: Argc, argv: *const AnyObject, _class: AnyObject) -> AnyObject {
+        unwrap_or_raise(|| {
+            let store = Value::from(0);
+            let bytes = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("2:").as_ptr(),
+                    &store,
+                    &bytes,
+                    &keywords,
+                )
+            };
+
+            let store = AnyObject::from(store).try_convert_to::<RubyStore>()?;
+            let bytes = AnyObject::from(bytes).try_convert_to::<RString>()?;
+            let keywords = Keywords::new(AnyObject::from(keywords), &["verify"])?;
+
+            let bytes = wat::parse_bytes(bytes.to_bytes_unchecked())
                 .map_err(to_ruby_err::<RuntimeError, _>)?;
 
-        Ok(Module::ruby_new(Module { inner: module }))
+            if let Some(keyring) = keywords.get("verify") {
+                keyring
+                    .try_convert_to::<RubyKeyring>()?
+                    .upcast()
+                    .verify(&bytes)?;
+            }
+
+            let module = wasmer::Module::new(store.upcast().inner(), &bytes)
+                .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+            Ok(Module::ruby_new(Module::raw_new(module, &bytes)))
+        })
+    }
+}
+
+/// Returns the names of the CPU features of `target`, sorted.
+fn cpu_feature_names(target: &wasmer::Target) -> Vec<String> {
+    let mut names = target
+        .cpu_features()
+        .iter()
+        .map(|cpu_feature| cpu_feature.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    names
+}
+
+/// Splits a serialized module, without its prefix, into its target
+/// triple, its CPU features and its artifact.
+fn split_serialized_module(bytes: &[u8]) -> Option<(&str, &str, &[u8])> {
+    fn split_field(bytes: &[u8]) -> Option<(&str, &[u8])> {
+        if bytes.len() < 4 {
+            return None;
+        }
+
+        let (length, bytes) = bytes.split_at(4);
+        let length = u32::from_le_bytes(length.try_into().ok()?) as usize;
+
+        if bytes.len() < length {
+            return None;
+        }
+
+        let (field, bytes) = bytes.split_at(length);
+
+        Some((std::str::from_utf8(field).ok()?, bytes))
     }
+
+    let (triple, bytes) = split_field(bytes)?;
+    let (cpu_features, artifact) = split_field(bytes)?;
+
+    Some((triple, cpu_features, artifact))
+}
+
+/// Checks that a module serialized for the `triple` target, with the
+/// `cpu_features` CPU features, can run on the target of a store.
+fn check_serialized_target(
+    target: &wasmer::Target,
+    triple: &str,
+    cpu_features: &str,
+) -> RubyResult<()> {
+    let expected_triple = target.triple().to_string();
+
+    if triple != expected_triple {
+        return Err(to_ruby_err::<RuntimeError, _>(format!(
+            "The module has been serialized for the `{}` target, but the store targets `{}`",
+            triple, expected_triple
+        )));
+    }
+
+    let expected_cpu_features = cpu_feature_names(target);
+    let missing_cpu_features = cpu_features
+        .split(',')
+        .filter(|cpu_feature| {
+            !cpu_feature.is_empty() && !expected_cpu_features.iter().any(|f| f == cpu_feature)
+        })
+        .collect::<Vec<_>>();
+
+    if !missing_cpu_features.is_empty() {
+        return Err(to_ruby_err::<RuntimeError, _>(format!(
+            "The module has been serialized for a target with the `{}` CPU features, which the store target doesn't have",
+            missing_cpu_features.join("`, `")
+        )));
+    }
+
+    Ok(())
 }
@@ -0,0 +1,304 @@
+use crate::{
+    error::{to_ruby_err, RuntimeError},
+    prelude::*,
+};
+use rutie::{AnyObject, Array, Encoding, Object, RString};
+
+/// Magic and version of a WebAssembly module.
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Identifier of a custom section.
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// Identifier of the export section.
+const EXPORT_SECTION_ID: u8 = 7;
+
+/// Identifier of the start section.
+const START_SECTION_ID: u8 = 8;
+
+enum Section {
+    Custom { name: String, data: Vec<u8> },
+    Known { id: u8, data: Vec<u8> },
+    Export(wasm_encoder::ExportSection),
+}
+
+impl Section {
+    fn is_custom(&self, expected_name: &str) -> bool {
+        matches!(self, Section::Custom { name, .. } if name == expected_name)
+    }
+
+    fn is_debug(&self) -> bool {
+        matches!(self, Section::Custom { name, .. } if name == "name" || name.starts_with(".debug_"))
+    }
+}
+
+#[rubyclass(module = "Wasmer")]
+pub struct ModuleBytes {
+    sections: Vec<Section>,
+}
+
+impl ModuleBytes {
+    pub(crate) fn from_bytes(bytes: &[u8]) -> RubyResult<Self> {
+        if !bytes.starts_with(HEADER) {
+            return Err(to_ruby_err::<RuntimeError, _>(
+                "The given bytes are not a WebAssembly module",
+            ));
+        }
+
+        Ok(Self {
+            sections: Self::parse(bytes).map_err(to_ruby_err::<RuntimeError, _>)?,
+        })
+    }
+
+    /// Removes all the custom sections with the given `name`, and
+    /// returns their data.
+    pub(crate) fn take_custom_sections(&mut self, name: &str) -> Vec<Vec<u8>> {
+        let mut taken = Vec::new();
+
+        self.sections.retain(|section| match section {
+            Section::Custom {
+                name: section_name,
+                data,
+            } if section_name == name => {
+                taken.push(data.clone());
+
+                false
+            }
+            _ => true,
+        });
+
+        taken
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Vec<Section>, wasmparser::BinaryReaderError> {
+        let mut reader = wasmparser::BinaryReader::new(&bytes[HEADER.len()..]);
+        let mut sections = Vec::new();
+
+        while !reader.eof() {
+            let id = reader.read_u8()? as u8;
+            let size = reader.read_var_u32()? as usize;
+            let data = reader.read_bytes(size)?;
+
+            sections.push(if id == CUSTOM_SECTION_ID {
+                let mut section_reader = wasmparser::BinaryReader::new(data);
+                let name = section_reader.read_string()?.to_string();
+                let data = section_reader.read_bytes(section_reader.bytes_remaining())?;
+
+                Section::Custom {
+                    name,
+                    data: data.to_vec(),
+                }
+            } else {
+                Section::Known {
+                    id,
+                    data: data.to_vec(),
+                }
+            });
+        }
+
+        Ok(sections)
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut module = wasm_encoder::Module::new();
+
+        for section in self.sections.iter() {
+            match section {
+                Section::Custom { name, data } => module.section(&wasm_encoder::CustomSection {
+                    name: name.as_str(),
+                    data: data.as_slice(),
+                }),
+                Section::Known { id, data } => module.section(&wasm_encoder::RawSection {
+                    id: *id,
+                    data: data.as_slice(),
+                }),
+                Section::Export(exports) => module.section(exports),
+            };
+        }
+
+        module.finish()
+    }
+
+    fn position_of(&self, expected_id: u8) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|section| matches!(section, Section::Known { id, .. } if *id == expected_id))
+    }
+
+    /// Removes the start section, and exports the start function as
+    /// `export_name` instead, so that it can be called later. Returns
+    /// `false` if the module has no start function.
+    pub(crate) fn defer_start_function(&mut self, export_name: &str) -> RubyResult<bool> {
+        let start_position = match self.position_of(START_SECTION_ID) {
+            Some(position) => position,
+            None => return Ok(false),
+        };
+        let export_position = self.position_of(EXPORT_SECTION_ID);
+
+        let function_index = match &self.sections[start_position] {
+            Section::Known { data, .. } => wasmparser::BinaryReader::new(data)
+                .read_var_u32()
+                .map_err(to_ruby_err::<RuntimeError, _>)?,
+            _ => unreachable!(),
+        };
+
+        let mut exports = wasm_encoder::ExportSection::new();
+
+        if let Some(Section::Known { data, .. }) = export_position.map(|p| &self.sections[p]) {
+            let reader = wasmparser::ExportSectionReader::new(data, 0)
+                .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+            for export in reader {
+                let export = export.map_err(to_ruby_err::<RuntimeError, _>)?;
+
+                exports.export(
+                    export.field,
+                    match export.kind {
+                        wasmparser::ExternalKind::Function => {
+                            wasm_encoder::Export::Function(export.index)
+                        }
+                        wasmparser::ExternalKind::Table => {
+                            wasm_encoder::Export::Table(export.index)
+                        }
+                        wasmparser::ExternalKind::Memory => {
+                            wasm_encoder::Export::Memory(export.index)
+                        }
+                        wasmparser::ExternalKind::Global => {
+                            wasm_encoder::Export::Global(export.index)
+                        }
+                        kind => {
+                            return Err(to_ruby_err::<RuntimeError, _>(format!(
+                                "Export `{}` has an unsupported kind `{:?}`",
+                                export.field, kind
+                            )))
+                        }
+                    },
+                );
+            }
+        }
+
+        exports.export(export_name, wasm_encoder::Export::Function(function_index));
+
+        // The export section comes right before the start section.
+        match export_position {
+            Some(export_position) => {
+                self.sections[export_position] = Section::Export(exports);
+                self.sections.remove(start_position);
+            }
+
+            None => self.sections[start_position] = Section::Export(exports),
+        }
+
+        Ok(true)
+    }
+}
+
+#[rubymethods]
+impl ModuleBytes {
+    pub fn new(bytes: &RString) -> RubyResult<AnyObject> {
+        let bytes =
+            wat::parse_bytes(bytes.to_bytes_unchecked()).map_err(to_ruby_err::<RuntimeError, _>)?;
+
+        Ok(ModuleBytes::ruby_new(ModuleBytes::from_bytes(&bytes)?))
+    }
+
+    pub fn custom_section_names(&self) -> RubyResult<Array> {
+        let mut names: Vec<&str> = Vec::new();
+
+        for section in self.sections.iter() {
+            if let Section::Custom { name, .. } = section {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+
+        Ok(names
+            .into_iter()
+            .map(|name| RString::new_utf8(name).to_any_object())
+            .collect())
+    }
+
+    pub fn custom_sections(&self, name: &RString) -> RubyResult<Array> {
+        let name = name.to_str();
+
+        Ok(self
+            .sections
+            .iter()
+            .filter_map(|section| match section {
+                Section::Custom {
+                    name: section_name,
+                    data,
+                } if section_name == name => {
+                    Some(RString::from_bytes(data, &Encoding::us_ascii()).to_any_object())
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn add_custom_section(
+        &mut self,
+        name: &RString,
+        data: &RString,
+    ) -> RubyResult<RubyModuleBytes> {
+        self.sections.push(Section::Custom {
+            name: name.to_string(),
+            data: data.to_bytes_unchecked().to_vec(),
+        });
+
+        Ok(_ruby_self)
+    }
+
+    pub fn replace_custom_section(
+        &mut self,
+        name: &RString,
+        data: &RString,
+    ) -> RubyResult<RubyModuleBytes> {
+        let name = name.to_str();
+        let section = Section::Custom {
+            name: name.to_string(),
+            data: data.to_bytes_unchecked().to_vec(),
+        };
+
+        // Keep only the first section with this name, and replace it.
+        let mut found = false;
+
+        self.sections.retain(|section| {
+            if !section.is_custom(name) {
+                return true;
+            }
+
+            !std::mem::replace(&mut found, true)
+        });
+
+        match self
+            .sections
+            .iter_mut()
+            .find(|section| section.is_custom(name))
+        {
+            Some(existing_section) => *existing_section = section,
+            None => self.sections.push(section),
+        }
+
+        Ok(_ruby_self)
+    }
+
+    pub fn strip_custom_section(&mut self, name: &RString) -> RubyResult<RubyModuleBytes> {
+        let name = name.to_str();
+
+        self.sections.retain(|section| !section.is_custom(name));
+
+        Ok(_ruby_self)
+    }
+
+    pub fn strip_debug_sections(&mut self) -> RubyResult<RubyModuleBytes> {
+        self.sections.retain(|section| !section.is_debug());
+
+        Ok(_ruby_self)
+    }
+
+    pub fn to_s(&self) -> RubyResult<RString> {
+        Ok(RString::from_bytes(&self.encode(), &Encoding::us_ascii()))
+    }
+}
@@ -1,9 +1,10 @@
-use crate::prelude::*;
+use crate::{features::Features, prelude::*, target::Target};
 use rutie::AnyObject;
 
 #[rubyclass(module = "Wasmer")]
 pub struct Store {
     inner: wasmer::Store,
+    features: wasmer::Features,
 }
 
 impl Store {
@@ -14,9 +15,81 @@ impl Store {
 
 #[rubymethods]
 impl Store {
-    pub fn new() -> RubyResult<AnyObject> {
-        Ok(Store::ruby_new(Store {
-            inner: Default::default(),
-        }))
+    pub fn features(&self) -> RubyResult<AnyObject> {
+        Ok(Features::ruby_new(Features::from(self.features.clone())))
+    }
+
+    pub fn target(&self) -> RubyResult<AnyObject> {
+        Ok(Target::ruby_new(Target::from(
+            self.inner().engine().target().clone(),
+        )))
+    }
+}
+
+pub(crate) mod ruby_store_extra {
+    use super::Store;
+    use crate::{
+        error::unwrap_or_raise, features::RubyFeatures, keywords::Keywords, target::RubyTarget,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn new(argc: Argc, argv: *const AnyObject, _class: AnyObject) -> AnyObject {
+        unwrap_or_raise(|| {
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(argc, argv_pointer, str_to_cstring(":").as_ptr(), &keywords)
+            };
+
+            let keywords = Keywords::new(AnyObject::from(keywords), &["features", "target"])?;
+
+            let (features, target) = (keywords.get("features"), keywords.get("target"));
+
+            // Without any configuration, the default engine and
+            // compiler are used.
+            if features.is_none() && target.is_none() {
+                return Ok(Store::ruby_new(Store {
+                    inner: wasmer::Store::default(),
+                    features: wasmer::Features::default(),
+                }));
+            }
+
+            let features = match features {
+                Some(features) => features
+                    .try_convert_to::<RubyFeatures>()?
+                    .upcast()
+                    .inner()
+                    .clone(),
+                None => wasmer::Features::default(),
+            };
+
+            let target = match target {
+                Some(target) => target
+                    .try_convert_to::<RubyTarget>()?
+                    .upcast()
+                    .inner()
+                    .clone(),
+                None => wasmer::Target::default(),
+            };
+
+            let engine = wasmer::Universal::new(wasmer::Cranelift::default())
+                .features(features.clone())
+                .target(target)
+                .engine();
+
+            Ok(Store::ruby_new(Store {
+                inner: wasmer::Store::new(&engine),
+                features,
+            }))
+        })
     }
 }
@@ -0,0 +1,64 @@
+use crate::{
+    error::{to_ruby_err, ArgumentError},
+    prelude::*,
+};
+use rutie::{AnyObject, Array, Object, RString};
+use std::str::FromStr;
+
+#[rubyclass(module = "Wasmer")]
+pub struct Target {
+    inner: wasmer::Target,
+}
+
+impl Target {
+    pub(crate) fn inner(&self) -> &wasmer::Target {
+        &self.inner
+    }
+}
+
+impl From<wasmer::Target> for Target {
+    fn from(inner: wasmer::Target) -> Self {
+        Self { inner }
+    }
+}
+
+#[rubymethods]
+impl Target {
+    pub fn new(triple: &RString, cpu_features: &Array) -> RubyResult<AnyObject> {
+        let triple =
+            wasmer::Triple::from_str(triple.to_str()).map_err(to_ruby_err::<ArgumentError, _>)?;
+        let mut features = wasmer::CpuFeature::set();
+
+        for nth in 0..cpu_features.length() {
+            let cpu_feature = cpu_features.at(nth as i64);
+
+            features.insert(
+                wasmer::CpuFeature::from_str(cpu_feature.try_convert_to::<RString>()?.to_str())
+                    .map_err(to_ruby_err::<ArgumentError, _>)?,
+            );
+        }
+
+        Ok(Target::ruby_new(Target {
+            inner: wasmer::Target::new(triple, features),
+        }))
+    }
+
+    pub fn host() -> RubyResult<AnyObject> {
+        Ok(Target::ruby_new(Target {
+            inner: wasmer::Target::default(),
+        }))
+    }
+
+    pub fn triple(&self) -> RubyResult<RString> {
+        Ok(RString::new_utf8(&self.inner().triple().to_string()))
+    }
+
+    pub fn cpu_features(&self) -> RubyResult<Array> {
+        Ok(self
+            .inner()
+            .cpu_features()
+            .iter()
+            .map(|cpu_feature| RString::new_utf8(&cpu_feature.to_string()).to_any_object())
+            .collect())
+    }
+}
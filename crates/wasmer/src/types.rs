@@ -386,7 +386,32 @@ impl TryFrom<wasmer::ImportType> for ImportType {
     }
 }
 
-fn extern_type_to_ruby_any_object(value: &wasmer::ExternType) -> AnyObject {
+#[rubyclass(module = "Wasmer")]
+pub struct FunctionInfo {
+    pub index: u32,
+    pub name: Option<String>,
+    pub ty: wasmer::FunctionType,
+}
+
+#[rubymethods]
+impl FunctionInfo {
+    pub fn index(&self) -> RubyResult<Integer> {
+        Ok(Integer::new(self.index.into()))
+    }
+
+    pub fn name(&self) -> RubyResult<AnyObject> {
+        Ok(self.name.as_ref().map_or_else(
+            || NilClass::new().to_any_object(),
+            |name| RString::new_utf8(name).to_any_object(),
+        ))
+    }
+
+    pub fn r#type(&self) -> RubyResult<AnyObject> {
+        Ok(FunctionType::ruby_new(FunctionType::from(&self.ty)))
+    }
+}
+
+pub(crate) fn extern_type_to_ruby_any_object(value: &wasmer::ExternType) -> AnyObject {
     match value {
         wasmer::ExternType::Function(t) => FunctionType::ruby_new(FunctionType::from(t)),
         wasmer::ExternType::Memory(t) => MemoryType::ruby_new(MemoryType::from(t)),
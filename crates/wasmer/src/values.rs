@@ -35,6 +35,19 @@ pub(crate) fn to_ruby_object(value: &wasmer::Value) -> AnyObject {
     }
 }
 
+/// Returns the zero value of a type, or a null reference.
+pub(crate) fn zero_value(ty: wasmer::Type) -> wasmer::Value {
+    match ty {
+        wasmer::Type::I32 => wasmer::Value::I32(0),
+        wasmer::Type::I64 => wasmer::Value::I64(0),
+        wasmer::Type::F32 => wasmer::Value::F32(0.0),
+        wasmer::Type::F64 => wasmer::Value::F64(0.0),
+        wasmer::Type::V128 => wasmer::Value::V128(0),
+        wasmer::Type::ExternRef => wasmer::Value::null(),
+        wasmer::Type::FuncRef => wasmer::Value::FuncRef(None),
+    }
+}
+
 #[rubyclass(module = "Wasmer")]
 pub struct Value {
     inner: wasmer::Value,
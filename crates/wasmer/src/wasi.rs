@@ -1,12 +1,24 @@
 use crate::{
-    error::{to_ruby_err, unwrap_or_raise, RuntimeError, TypeError},
-    import_object::ImportObject,
-    module::Module,
+    error::{
+        protect, to_ruby_err, unwrap_or_raise, ArgumentError, NameError, RuntimeError, TypeError,
+    },
+    import_object::{to_import_name, to_registered_namespace, ImportObject},
+    instance::to_instantiation_err,
+    keywords::Keywords,
+    module::{Module, RubyModule},
     prelude::*,
-    store::Store,
 };
-use rutie::{AnyObject, Array, Boolean, Hash, Integer, NilClass, Object, RString};
-use std::{convert::TryFrom, path::PathBuf};
+use rutie::{
+    AnyException, AnyObject, Array, Encoding, Hash, Integer, NilClass, Object, RString, Symbol,
+};
+use std::{
+    convert::TryFrom,
+    fmt,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use wasmer_vfs::FileSystem;
 
 #[derive(Debug, Copy, Clone)]
 #[repr(u8)]
@@ -24,6 +36,14 @@ impl Version {
             Self::Snapshot1 => Integer::new(3),
         }
     }
+
+    fn to_symbol(&self) -> Symbol {
+        Symbol::new(match self {
+            Self::Latest => "latest",
+            Self::Snapshot0 => "snapshot0",
+            Self::Snapshot1 => "snapshot1",
+        })
+    }
 }
 
 impl From<&wasmer_wasi::WasiVersion> for Version {
@@ -59,9 +79,131 @@ impl TryFrom<&Integer> for Version {
     }
 }
 
-#[rubyclass(module = "Wasmer::Wasi")]
+impl TryFrom<&Symbol> for Version {
+    type Error = &'static str;
+
+    fn try_from(value: &Symbol) -> Result<Self, Self::Error> {
+        Ok(match value.to_str() {
+            "latest" => Version::Latest,
+            "snapshot0" => Version::Snapshot0,
+            "snapshot1" => Version::Snapshot1,
+            _ => {
+                return Err(
+                    "Unrecognized WASI version (expects `:latest`, `:snapshot0` or `:snapshot1`)",
+                )
+            }
+        })
+    }
+}
+
+/// Reads a WASI version, given as a `Version` constant, as a `Symbol`
+/// (e.g. `:snapshot1`), or as a `Module` to detect the version from.
+fn to_wasi_version(value: &AnyObject) -> RubyResult<wasmer_wasi::WasiVersion> {
+    if let Ok(module) = value.try_convert_to::<RubyModule>() {
+        return wasmer_wasi::get_wasi_version(module.upcast().inner(), false).ok_or_else(|| {
+            to_ruby_err::<ArgumentError, _>(
+                "The WASI version cannot be detected, because the module does not import any WASI namespace",
+            )
+        });
+    }
+
+    let version = if let Ok(integer) = value.try_convert_to::<Integer>() {
+        Version::try_from(&integer).map(Into::into)
+    } else if let Ok(symbol) = value.try_convert_to::<Symbol>() {
+        Version::try_from(&symbol).map(Into::into)
+    } else {
+        return Err(to_ruby_err::<TypeError, _>(format!(
+            "The WASI version has an invalid type `{:?}` (expects a `Version` constant, a `Symbol` or a `Module`)",
+            value.ty()
+        )));
+    };
+
+    version.map_err(to_ruby_err::<TypeError, _>)
+}
+
+#[rubyclass(module = "Wasmer::Wasi", mark)]
 pub struct StateBuilder {
     inner: wasmer_wasi::WasiStateBuilder,
+    /// The Ruby `IO` the standard input is read from, if any.
+    stdin: Option<AnyObject>,
+    /// The bytes of the standard input, if it is given as a `String`.
+    stdin_bytes: Option<Vec<u8>>,
+    /// Whether the standard output is captured.
+    capture_stdout: bool,
+    /// Whether the standard error is captured.
+    capture_stderr: bool,
+    /// The in-memory filesystems, with their guest path.
+    mounts: Vec<(PathBuf, wasmer_vfs::mem_fs::FileSystem)>,
+    /// The preopened directories, with their guest name and their
+    /// host path.
+    preopens: Vec<(String, PathBuf)>,
+    /// The clock replacing the clocks of the host, if any.
+    clock: Option<Clock>,
+    /// The seed of the random numbers, if they are deterministic.
+    random_seed: Option<u64>,
+    /// The tracer the WASI calls are reported to, if any.
+    tracer: Option<Tracer>,
+}
+
+impl GcMark for StateBuilder {
+    fn gc_mark(&self) {
+        self.stdin.gc_mark();
+
+        if let Some(clock) = &self.clock {
+            clock.gc_mark();
+        }
+
+        if let Some(tracer) = &self.tracer {
+            tracer.gc_mark();
+        }
+    }
+}
+
+impl StateBuilder {
+    /// Preopens `directory`, exposed to the guest as `alias` if any,
+    /// with the permissions given by the `read`, `write` and `create`
+    /// keywords. All permissions are granted by default; `create`
+    /// follows `write` unless it is given.
+    fn preopen(
+        &mut self,
+        directory: &str,
+        alias: Option<&str>,
+        keywords: &Keywords,
+    ) -> RubyResult<()> {
+        let read = keywords.get_bool("read", true)?;
+        let write = keywords.get_bool("write", true)?;
+        let create = keywords.get_bool("create", write)?;
+
+        if create && !write {
+            return Err(to_ruby_err::<ArgumentError, _>(format!(
+                "The directory `{}` cannot be preopened with `create: true` and `write: false`, as creating implies writing",
+                directory
+            )));
+        }
+
+        self.inner
+            .preopen(|preopen| {
+                if let Some(alias) = alias {
+                    preopen.alias(alias);
+                }
+
+                preopen
+                    .directory(directory)
+                    .read(read)
+                    .write(write)
+                    .create(create)
+            })
+            .map_err(to_ruby_err::<RuntimeError, _>)?;
+        self.preopens.push((
+            alias
+                .map(|alias| alias.trim_start_matches('/'))
+                .unwrap_or(directory)
+                .to_string(),
+            PathBuf::from(directory),
+        ));
+
+        Ok(())
+    }
 }
 
 #[rubymethods]
@@ -69,6 +211,15 @@ impl StateBuilder {
     pub fn new(program_name: &RString) -> RubyResult<AnyObject> {
         Ok(StateBuilder::ruby_new(StateBuilder {
             inner: wasmer_wasi::WasiState::new(program_name.to_str()),
+            stdin: None,
+            stdin_bytes: None,
+            capture_stdout: false,
+            capture_stderr: false,
+            mounts: Vec::new(),
+            preopens: Vec::new(),
+            clock: None,
+            random_seed: None,
+            tracer: None,
         }))
     }
 
@@ -122,29 +273,19 @@ impl StateBuilder {
         &mut self,
         preopen_directories: &Array,
     ) -> RubyResult<RubyStateBuilder> {
-        self.inner
-            .preopen_dirs(
-                unsafe { preopen_directories.to_any_object().to::<Array>() }
-                    .into_iter()
-                    .map(|directory| {
-                        Ok(PathBuf::from(
-                            directory.try_convert_to::<RString>()?.to_string(),
-                        ))
-                    })
-                    .collect::<RubyResult<Vec<_>>>()?,
-            )
-            .map_err(to_ruby_err::<RuntimeError, _>)?;
-
-        Ok(_ruby_self)
-    }
+        let directories = unsafe { preopen_directories.to_any_object().to::<Array>() }
+            .into_iter()
+            .map(|directory| Ok(directory.try_convert_to::<RString>()?.to_string()))
+            .collect::<RubyResult<Vec<_>>>()?;
 
-    pub fn preopen_directory(
-        &mut self,
-        preopen_directory: &RString,
-    ) -> RubyResult<RubyStateBuilder> {
         self.inner
-            .preopen_dir(preopen_directory.to_str())
+            .preopen_dirs(directories.iter().map(PathBuf::from))
             .map_err(to_ruby_err::<RuntimeError, _>)?;
+        self.preopens.extend(
+            directories
+                .into_iter()
+                .map(|directory| (directory.clone(), PathBuf::from(directory))),
+        );
 
         Ok(_ruby_self)
     }
@@ -164,63 +305,1937 @@ impl StateBuilder {
         });
 
         self.inner
-            .map_dirs(map_directory_pairs)
+            .map_dirs(map_directory_pairs.clone())
             .map_err(to_ruby_err::<RuntimeError, _>)?;
+        self.preopens.extend(
+            map_directory_pairs
+                .into_iter()
+                .map(|(alias, directory)| (alias.trim_start_matches('/').to_string(), directory)),
+        );
 
         Ok(_ruby_self)
     }
 
-    pub fn map_directory(
-        &mut self,
-        alias: &RString,
-        directory: &RString,
-    ) -> RubyResult<RubyStateBuilder> {
+    pub fn capture_stdout(&mut self) -> RubyResult<RubyStateBuilder> {
+        self.capture_stdout = true;
+
+        Ok(_ruby_self)
+    }
+
+    pub fn capture_stderr(&mut self) -> RubyResult<RubyStateBuilder> {
+        self.capture_stderr = true;
+
+        Ok(_ruby_self)
+    }
+
+    pub fn random_seed(&mut self, seed: &Integer) -> RubyResult<RubyStateBuilder> {
+        self.random_seed = Some(seed.to_u64());
+
+        Ok(_ruby_self)
+    }
+
+    pub fn stdin(&mut self, stdin: &AnyObject) -> RubyResult<RubyStateBuilder> {
+        if let Ok(string) = stdin.try_convert_to::<RString>() {
+            self.stdin = None;
+            self.stdin_bytes = Some(string.to_bytes_unchecked().to_vec());
+        } else if stdin.respond_to("read") {
+            self.stdin = Some(stdin.clone());
+            self.stdin_bytes = None;
+        } else {
+            return Err(to_ruby_err::<TypeError, _>(format!(
+                "The standard input must be a `String` or an `IO`, not a `{:?}`",
+                stdin.ty()
+            )));
+        }
+
+        Ok(_ruby_self)
+    }
+
+    pub fn mount(&mut self, guest_path: &RString, memfs: &MemFS) -> RubyResult<RubyStateBuilder> {
+        let guest_path = PathBuf::from(guest_path.to_str());
+
+        if !guest_path.has_root() {
+            return Err(to_ruby_err::<ArgumentError, _>(format!(
+                "The mount point `{}` must be an absolute path",
+                guest_path.display()
+            )));
+        }
+
         self.inner
-            .map_dir(alias.to_str(), PathBuf::from(directory.to_str()))
+            .preopen(|preopen| {
+                preopen
+                    .directory(&guest_path)
+                    .read(true)
+                    .write(true)
+                    .create(true)
+            })
             .map_err(to_ruby_err::<RuntimeError, _>)?;
+        self.preopens.push((
+            guest_path.to_string_lossy().into_owned(),
+            guest_path.clone(),
+        ));
+        self.mounts.push((guest_path, memfs.inner.clone()));
 
         Ok(_ruby_self)
     }
 
     pub fn finalize(&mut self) -> RubyResult<AnyObject> {
+        // The standard streams and the filesystem are reset by each
+        // `finalize`, so they are set again every time.
+        if let Some(stdin_bytes) = &self.stdin_bytes {
+            let mut pipe = wasmer_wasi::Pipe::new();
+            pipe.write_all(stdin_bytes)
+                .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+            self.inner.stdin(Box::new(pipe));
+        } else if let Some(stdin) = &self.stdin {
+            self.inner.stdin(Box::new(IoFile { io: stdin.clone() }));
+        }
+
+        if self.capture_stdout {
+            self.inner.stdout(Box::new(wasmer_wasi::Pipe::new()));
+        }
+
+        if self.capture_stderr {
+            self.inner.stderr(Box::new(wasmer_wasi::Pipe::new()));
+        }
+
+        if !self.mounts.is_empty() {
+            self.inner.set_fs(Box::new(MountedFileSystem {
+                mounts: self.mounts.clone(),
+                host: Default::default(),
+            }));
+        }
+
         Ok(Environment::ruby_new(Environment {
             inner: self
                 .inner
                 .finalize()
                 .map_err(to_ruby_err::<RuntimeError, _>)?,
+            stdin: self.stdin.clone(),
+            preopens: self.preopens.clone(),
+            clock: self.clock.clone(),
+            random_seed: self.random_seed,
+            tracer: self.tracer.clone(),
         }))
     }
 }
 
+pub(crate) mod ruby_statebuilder_extra {
+    use super::{Clock, Tracer};
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, ArgumentError},
+        keywords::Keywords,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object, RString, Symbol, VM,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    /// The keywords accepted by `preopen_directory` and
+    /// `map_directory`.
+    const PERMISSIONS: &[&str] = &["read", "write", "create"];
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn preopen_directory(
+        argc: Argc,
+        argv: *const AnyObject,
+        mut itself: super::RubyStateBuilder,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let directory = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("1:").as_ptr(),
+                    &directory,
+                    &keywords,
+                )
+            };
+
+            let directory = AnyObject::from(directory).try_convert_to::<RString>()?;
+            let keywords = Keywords::new(AnyObject::from(keywords), PERMISSIONS)?;
+
+            itself
+                .upcast_mut()
+                .preopen(directory.to_str(), None, &keywords)?;
+
+            Ok(itself.to_any_object())
+        })
+    }
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn clock(
+        argc: Argc,
+        argv: *const AnyObject,
+        mut itself: super::RubyStateBuilder,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let kind = Value::from(0);
+            let time = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("02").as_ptr(),
+                    &kind,
+                    &time,
+                )
+            };
+
+            let kind = AnyObject::from(kind);
+            let time = AnyObject::from(time);
+            let is_fixed = kind
+                .try_convert_to::<Symbol>()
+                .map(|kind| kind.to_str() == "fixed")
+                .unwrap_or(false);
+
+            let clock = if is_fixed && !time.is_nil() {
+                Clock::Fixed(super::to_nanoseconds(&time)?)
+            } else if kind.is_nil() && VM::is_block_given() {
+                Clock::Callback(VM::block_proc().to_any_object())
+            } else {
+                return Err(to_ruby_err::<ArgumentError, _>(
+                    "`StateBuilder#clock` expects either `:fixed` and a time, or a block",
+                ));
+            };
+
+            itself.upcast_mut().clock = Some(clock);
+
+            Ok(itself.to_any_object())
+        })
+    }
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn trace(
+        argc: Argc,
+        argv: *const AnyObject,
+        mut itself: super::RubyStateBuilder,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let io = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(argc, argv_pointer, str_to_cstring("01").as_ptr(), &io)
+            };
+
+            let io = AnyObject::from(io);
+
+            let tracer = if !io.is_nil() && io.respond_to("write") {
+                Tracer::Io(io)
+            } else if io.is_nil() && VM::is_block_given() {
+                Tracer::Callback(VM::block_proc().to_any_object())
+            } else {
+                return Err(to_ruby_err::<ArgumentError, _>(
+                    "`StateBuilder#trace` expects either an `IO`, or a block",
+                ));
+            };
+
+            itself.upcast_mut().tracer = Some(tracer);
+
+            Ok(itself.to_any_object())
+        })
+    }
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn map_directory(
+        argc: Argc,
+        argv: *const AnyObject,
+        mut itself: super::RubyStateBuilder,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let alias = Value::from(0);
+            let directory = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("2:").as_ptr(),
+                    &alias,
+                    &directory,
+                    &keywords,
+                )
+            };
+
+            let alias = AnyObject::from(alias).try_convert_to::<RString>()?;
+            let directory = AnyObject::from(directory).try_convert_to::<RString>()?;
+            let keywords = Keywords::new(AnyObject::from(keywords), PERMISSIONS)?;
+
+            itself
+                .upcast_mut()
+                .preopen(directory.to_str(), Some(alias.to_str()), &keywords)?;
+
+            Ok(itself.to_any_object())
+        })
+    }
+}
+
+/// A file reading lazily from a Ruby `IO`, or any object with a
+/// `read(length)` method like `StringIO`.
+struct IoFile {
+    io: AnyObject,
+}
+
+// The file is only used by the WASI imports, which are called from
+// the Ruby thread.
+unsafe impl Send for IoFile {}
+
+impl fmt::Debug for IoFile {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("IoFile")
+    }
+}
+
+impl io::Read for IoFile {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = Integer::new(buffer.len() as i64).to_any_object();
+
+        // An exception raised by the `IO` becomes an I/O error, so
+        // that the guest gets an errno.
+        let chunk = protect(|| unsafe { self.io.send("read", &[length.clone()]) })
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()))?;
+
+        // `nil` means the end of the file.
+        if chunk.is_nil() {
+            return Ok(0);
+        }
+
+        let chunk = chunk
+            .try_convert_to::<RString>()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let chunk = chunk.to_bytes_unchecked();
+        let length = chunk.len().min(buffer.len());
+
+        buffer[..length].copy_from_slice(&chunk[..length]);
+
+        Ok(length)
+    }
+}
+
+impl io::Write for IoFile {
+    fn write(&mut self, _buffer: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "The standard input cannot be written",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Seek for IoFile {
+    fn seek(&mut self, _position: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "The standard input cannot be sought",
+        ))
+    }
+}
+
+impl wasmer_wasi::VirtualFile for IoFile {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+
+    fn last_modified(&self) -> u64 {
+        0
+    }
+
+    fn created_time(&self) -> u64 {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        0
+    }
+
+    fn set_len(&mut self, _new_size: u64) -> Result<(), wasmer_wasi::FsError> {
+        Err(wasmer_wasi::FsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<(), wasmer_wasi::FsError> {
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, wasmer_wasi::FsError> {
+        Ok(0)
+    }
+}
+
 #[rubyclass(module = "Wasmer::Wasi")]
+pub struct MemFS {
+    inner: wasmer_vfs::mem_fs::FileSystem,
+}
+
+#[rubymethods]
+impl MemFS {
+    pub fn new() -> RubyResult<AnyObject> {
+        Ok(MemFS::ruby_new(MemFS {
+            inner: Default::default(),
+        }))
+    }
+
+    pub fn write_file(&self, path: &RString, content: &RString) -> RubyResult<RubyMemFS> {
+        let path = to_memfs_path(path);
+
+        self.inner
+            .new_open_options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .and_then(|mut file| Ok(file.write_all(content.to_bytes_unchecked())?))
+            .map_err(|error| to_memfs_err("write", &path, error))?;
+
+        Ok(_ruby_self)
+    }
+
+    pub fn read_file(&self, path: &RString) -> RubyResult<RString> {
+        let path = to_memfs_path(path);
+        let mut bytes = Vec::new();
+
+        self.inner
+            .new_open_options()
+            .read(true)
+            .open(&path)
+            .and_then(|mut file| Ok(file.read_to_end(&mut bytes)?))
+            .map_err(|error| to_memfs_err("read", &path, error))?;
+
+        Ok(RString::from_bytes(&bytes, &Encoding::utf8()))
+    }
+
+    pub fn mkdir(&self, path: &RString) -> RubyResult<RubyMemFS> {
+        let path = to_memfs_path(path);
+
+        self.inner
+            .create_dir(&path)
+            .map_err(|error| to_memfs_err("create the directory", &path, error))?;
+
+        Ok(_ruby_self)
+    }
+}
+
+impl MemFS {
+    /// Returns the names of the entries of the directory at `path`,
+    /// sorted.
+    fn list(&self, path: &Path) -> RubyResult<Array> {
+        let mut names = self
+            .inner
+            .read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                    .collect::<Result<Vec<_>, wasmer_vfs::FsError>>()
+            })
+            .map_err(|error| to_memfs_err("list", path, error))?;
+
+        names.sort();
+
+        Ok(names
+            .into_iter()
+            .map(|name| RString::new_utf8(&name).to_any_object())
+            .collect())
+    }
+}
+
+pub(crate) mod ruby_memfs_extra {
+    use crate::error::unwrap_or_raise;
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Object, RString,
+    };
+    use rutie_derive::UpcastRubyClass;
+    use std::path::PathBuf;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn list(
+        argc: Argc,
+        argv: *const AnyObject,
+        itself: super::RubyMemFS,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let path = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(argc, argv_pointer, str_to_cstring("01").as_ptr(), &path)
+            };
+
+            let path = AnyObject::from(path);
+            let path = if path.is_nil() {
+                PathBuf::from("/")
+            } else {
+                super::to_memfs_path(&path.try_convert_to::<RString>()?)
+            };
+
+            Ok(itself.upcast().list(&path)?.to_any_object())
+        })
+    }
+}
+
+/// Reads a path of an in-memory filesystem. Relative paths are
+/// relative to the root.
+fn to_memfs_path(path: &RString) -> PathBuf {
+    Path::new("/").join(path.to_str())
+}
+
+fn to_memfs_err(action: &str, path: &Path, error: wasmer_vfs::FsError) -> AnyException {
+    to_ruby_err::<RuntimeError, _>(format!("Cannot {} `{}`: {}", action, path.display(), error))
+}
+
+/// The filesystem of a guest with mounted in-memory filesystems: a
+/// path below a mount point goes to the in-memory filesystem, any
+/// other path goes to the host filesystem.
+#[derive(Debug, Clone)]
+struct MountedFileSystem {
+    mounts: Vec<(PathBuf, wasmer_vfs::mem_fs::FileSystem)>,
+    host: wasmer_vfs::host_fs::FileSystem,
+}
+
+impl MountedFileSystem {
+    /// Returns the index of the mount owning `path` (if any), the
+    /// filesystem owning `path`, and `path` within this filesystem.
+    /// When mount points are nested, the deepest one wins.
+    fn route(&self, path: &Path) -> (Option<usize>, &dyn wasmer_vfs::FileSystem, PathBuf) {
+        let mount = self
+            .mounts
+            .iter()
+            .enumerate()
+            .filter(|(_, (mount_point, _))| path.starts_with(mount_point))
+            .max_by_key(|(_, (mount_point, _))| mount_point.components().count());
+
+        match mount {
+            Some((index, (mount_point, filesystem))) => (
+                Some(index),
+                filesystem,
+                Path::new("/").join(path.strip_prefix(mount_point).unwrap_or(path)),
+            ),
+            None => (None, &self.host, path.to_path_buf()),
+        }
+    }
+}
+
+impl wasmer_vfs::FileSystem for MountedFileSystem {
+    fn read_dir(&self, path: &Path) -> Result<wasmer_vfs::ReadDir, wasmer_vfs::FsError> {
+        let (mount, filesystem, inner_path) = self.route(path);
+        let entries = filesystem.read_dir(&inner_path)?;
+
+        if mount.is_none() {
+            return Ok(entries);
+        }
+
+        // Entries of an in-memory filesystem are seen from the
+        // mount point.
+        Ok(wasmer_vfs::ReadDir::new(
+            entries
+                .map(|entry| {
+                    let mut entry = entry?;
+                    entry.path = path.join(entry.path.file_name().unwrap_or_default());
+
+                    Ok(entry)
+                })
+                .collect::<Result<Vec<_>, wasmer_vfs::FsError>>()?,
+        ))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.route(path);
+
+        filesystem.create_dir(&path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.route(path);
+
+        filesystem.remove_dir(&path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), wasmer_vfs::FsError> {
+        let (from_mount, filesystem, from) = self.route(from);
+        let (to_mount, _, to) = self.route(to);
+
+        // Files cannot be moved from one filesystem to another.
+        if from_mount != to_mount {
+            return Err(wasmer_vfs::FsError::PermissionDenied);
+        }
+
+        filesystem.rename(&from, &to)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<wasmer_vfs::Metadata, wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.route(path);
+
+        filesystem.metadata(&path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<wasmer_vfs::Metadata, wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.route(path);
+
+        filesystem.symlink_metadata(&path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.route(path);
+
+        filesystem.remove_file(&path)
+    }
+
+    fn new_open_options(&self) -> wasmer_vfs::OpenOptions {
+        wasmer_vfs::OpenOptions::new(Box::new(MountedFileOpener {
+            filesystem: self.clone(),
+        }))
+    }
+}
+
+struct MountedFileOpener {
+    filesystem: MountedFileSystem,
+}
+
+impl wasmer_vfs::FileOpener for MountedFileOpener {
+    fn open(
+        &mut self,
+        path: &Path,
+        config: &wasmer_vfs::OpenOptionsConfig,
+    ) -> Result<Box<dyn wasmer_vfs::VirtualFile>, wasmer_vfs::FsError> {
+        let (_, filesystem, path) = self.filesystem.route(path);
+
+        filesystem
+            .new_open_options()
+            .read(config.read())
+            .write(config.write())
+            .create_new(config.create_new())
+            .create(config.create())
+            .append(config.append())
+            .truncate(config.truncate())
+            .open(&path)
+    }
+}
+
+#[rubyclass(module = "Wasmer::Wasi", mark)]
 pub struct Environment {
     inner: wasmer_wasi::WasiEnv,
+    /// The Ruby `IO` the standard input is read from, if any.
+    stdin: Option<AnyObject>,
+    /// The preopened directories, with their guest name and their
+    /// host path.
+    preopens: Vec<(String, PathBuf)>,
+    /// The clock replacing the clocks of the host, if any.
+    clock: Option<Clock>,
+    /// The seed of the random numbers, if they are deterministic.
+    random_seed: Option<u64>,
+    /// The tracer the WASI calls are reported to, if any.
+    tracer: Option<Tracer>,
+}
+
+impl GcMark for Environment {
+    fn gc_mark(&self) {
+        self.stdin.gc_mark();
+
+        if let Some(clock) = &self.clock {
+            clock.gc_mark();
+        }
+
+        if let Some(tracer) = &self.tracer {
+            tracer.gc_mark();
+        }
+    }
+}
+
+impl Environment {
+    /// Generates the WASI imports of this environment. The state is
+    /// shared by all the generated imports, so that one environment
+    /// can be used for several runs.
+    fn import_object(
+        &self,
+        store: &wasmer::Store,
+        wasi_version: wasmer_wasi::WasiVersion,
+    ) -> wasmer::ImportObject {
+        let functions = WasiFunctions {
+            version: wasi_version,
+            clock: self.clock.clone(),
+            random_seed: self.random_seed,
+        };
+        let mut namespace = functions.namespace(store, &self.inner);
+
+        if let Some(tracer) = &self.tracer {
+            let mut traced_namespace = wasmer::Exports::new();
+            let wasi_namespace = WasiNamespace::default();
+
+            for (name, r#extern) in namespace.iter() {
+                match r#extern {
+                    wasmer::Extern::Function(function) => traced_namespace.insert(
+                        name.as_str(),
+                        traced_function(
+                            store,
+                            TracedEnvironment {
+                                wasi_env: self.inner.clone(),
+                                functions: functions.clone(),
+                                wasi_namespace: wasi_namespace.clone(),
+                                syscall: name.clone(),
+                                tracer: tracer.clone(),
+                                function: None,
+                            },
+                            function.ty(),
+                        ),
+                    ),
+                    _ => traced_namespace.insert(name.as_str(), r#extern.clone()),
+                }
+            }
+
+            namespace = traced_namespace;
+        }
+
+        let mut import_object = wasmer::ImportObject::new();
+        import_object.register(functions.namespace_name(), namespace);
+
+        import_object
+    }
+
+    /// Generates the WASI imports of this environment, merged with the
+    /// `extra` namespaces, given as a `Hash` of namespaces.
+    fn import_object_with_extra(
+        &self,
+        store: &wasmer::Store,
+        wasi_version: wasmer_wasi::WasiVersion,
+        extra: Option<&Hash>,
+    ) -> RubyResult<ImportObject> {
+        let mut import_object = ImportObject::raw_new(self.import_object(store, wasi_version));
+
+        if let Some(extra) = extra {
+            let mut result: RubyResult<()> = Ok(());
+
+            extra.each(|namespace_name, namespace| {
+                if result.is_err() {
+                    return;
+                }
+
+                result = (|| {
+                    let namespace_name = to_import_name(&namespace_name)?;
+                    let namespace = to_registered_namespace(&namespace_name, &namespace)?;
+
+                    import_object.extend_namespace(&namespace_name, namespace)
+                })();
+            });
+
+            result?;
+        }
+
+        Ok(import_object)
+    }
+
+    /// Returns the arguments, without the program name.
+    fn read_arguments(&self) -> Array {
+        let state = self.inner.state();
+
+        // The first argument is the program name.
+        state
+            .args
+            .iter()
+            .skip(1)
+            .map(|argument| RString::from_bytes(argument, &Encoding::utf8()).to_any_object())
+            .collect()
+    }
+
+    fn read_environment_variables(&self) -> Hash {
+        let state = self.inner.state();
+        let mut environment_variables = Hash::new();
+
+        for pair in state.envs.iter() {
+            let mut pair = pair.splitn(2, |byte| *byte == b'=');
+            let key = pair.next().unwrap_or_default();
+            let value = pair.next().unwrap_or_default();
+
+            environment_variables.store(
+                RString::from_bytes(key, &Encoding::utf8()),
+                RString::from_bytes(value, &Encoding::utf8()),
+            );
+        }
+
+        environment_variables
+    }
+
+    /// Returns the host path of `guest_path`, resolved from the
+    /// preopened directories.
+    fn resolve_path(&self, guest_path: &str) -> RubyResult<PathBuf> {
+        let guest_path = Path::new(guest_path.trim_start_matches('/'));
+
+        self.preopens
+            .iter()
+            .filter_map(|(name, host_path)| {
+                let name = Path::new(name.trim_start_matches('/'));
+
+                // `.` holds the relative paths of the guest.
+                let relative_path = if name == Path::new(".") {
+                    Some(guest_path)
+                } else {
+                    guest_path.strip_prefix(name).ok()
+                };
+
+                relative_path.map(|relative_path| {
+                    (
+                        name.components()
+                            .filter(|component| *component != Component::CurDir)
+                            .count(),
+                        host_path.join(relative_path),
+                    )
+                })
+            })
+            // The most specific preopened directory wins.
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, host_path)| host_path)
+            .ok_or_else(|| {
+                to_ruby_err::<NameError, _>(format!(
+                    "The path `{}` is not in a preopened directory",
+                    guest_path.display()
+                ))
+            })
+    }
 }
 
 #[rubymethods]
 impl Environment {
-    pub fn generate_import_object(
+    pub fn run(&self, module: &Module) -> RubyResult<Integer> {
+        let module = module.inner();
+        let wasi_version = wasmer_wasi::get_wasi_version(module, false).ok_or_else(|| {
+            to_ruby_err::<TypeError, _>("The module does not import any WASI namespace")
+        })?;
+        let import_object = self.import_object(module.store(), wasi_version);
+        let instance =
+            wasmer::Instance::new(module, &import_object).map_err(to_instantiation_err)?;
+        let start = instance.exports.get_function("_start").map_err(|_| {
+            to_ruby_err::<NameError, _>(
+                "The module does not export a `_start` function, is it a WASI command?",
+            )
+        })?;
+
+        match start.call(&[]) {
+            Ok(_) => Ok(Integer::new(0)),
+
+            // `proc_exit` stops the program with a trap holding the
+            // exit code.
+            Err(error) => match error.downcast::<wasmer_wasi::WasiError>() {
+                Ok(wasmer_wasi::WasiError::Exit(exit_code)) => {
+                    Ok(Integer::new(i64::from(exit_code)))
+                }
+                Ok(error) => Err(to_ruby_err::<RuntimeError, _>(error)),
+                Err(error) => Err(to_ruby_err::<RuntimeError, _>(error)),
+            },
+        }
+    }
+
+    pub fn arguments(&self) -> RubyResult<Array> {
+        Ok(self.read_arguments())
+    }
+
+    pub fn set_arguments(&self, arguments: &Array) -> RubyResult<Array> {
+        let arguments = unsafe { arguments.to_any_object().to::<Array>() }
+            .into_iter()
+            .map(|argument| {
+                let argument = argument.try_convert_to::<RString>()?;
+                let argument = argument.to_bytes_unchecked();
+
+                if argument.contains(&0) {
+                    return Err(to_ruby_err::<ArgumentError, _>(
+                        "Arguments must not contain the nul byte",
+                    ));
+                }
+
+                Ok(argument.to_vec())
+            })
+            .collect::<RubyResult<Vec<_>>>()?;
+
+        {
+            let mut state = self.inner.state();
+            state.args.truncate(1);
+            state.args.extend(arguments);
+        }
+
+        Ok(self.read_arguments())
+    }
+
+    pub fn environment_variables(&self) -> RubyResult<Hash> {
+        Ok(self.read_environment_variables())
+    }
+
+    pub fn set_environment_variables(&self, environment_variables: &Hash) -> RubyResult<Hash> {
+        let mut pairs = Vec::with_capacity(environment_variables.length());
+        let mut result: RubyResult<()> = Ok(());
+
+        environment_variables.each(|key, value| {
+            if result.is_err() {
+                return;
+            }
+
+            result = (|| {
+                let key = key.try_convert_to::<RString>()?;
+                let value = value.try_convert_to::<RString>()?;
+                let (key, value) = (key.to_bytes_unchecked(), value.to_bytes_unchecked());
+
+                if key.contains(&0) || key.contains(&b'=') || value.contains(&0) {
+                    return Err(to_ruby_err::<ArgumentError, _>(format!(
+                        "The environment variable `{}` must not contain the nul byte, or `=` in its key",
+                        String::from_utf8_lossy(key)
+                    )));
+                }
+
+                let mut pair = Vec::with_capacity(key.len() + value.len() + 1);
+                pair.extend_from_slice(key);
+                pair.push(b'=');
+                pair.extend_from_slice(value);
+                pairs.push(pair);
+
+                Ok(())
+            })();
+        });
+
+        result?;
+        self.inner.state().envs = pairs;
+
+        Ok(self.read_environment_variables())
+    }
+
+    pub fn file_descriptors(&self) -> RubyResult<Hash> {
+        let state = self.inner.state();
+        let mut file_descriptors = state
+            .fs
+            .fd_map
+            .iter()
+            .filter_map(|(fd, fd_entry)| {
+                state
+                    .fs
+                    .inodes
+                    .get(fd_entry.inode)
+                    .map(|inode| (*fd, inode.name.clone()))
+            })
+            .collect::<Vec<_>>();
+        file_descriptors.sort();
+
+        let mut hash = Hash::new();
+
+        for (fd, name) in file_descriptors {
+            hash.store(Integer::new(fd.into()), RString::new_utf8(&name));
+        }
+
+        Ok(hash)
+    }
+
+    pub fn preopened_directories(&self) -> RubyResult<Hash> {
+        let mut preopened_directories = Hash::new();
+
+        for (name, host_path) in self.preopens.iter() {
+            preopened_directories.store(
+                RString::new_utf8(name),
+                RString::new_utf8(&host_path.to_string_lossy()),
+            );
+        }
+
+        Ok(preopened_directories)
+    }
+
+    pub fn read_file(&self, guest_path: &RString) -> RubyResult<RString> {
+        let host_path = self.resolve_path(guest_path.to_str())?;
+        let mut bytes = Vec::new();
+
+        self.inner
+            .state()
+            .fs
+            .fs_backing
+            .new_open_options()
+            .read(true)
+            .open(&host_path)
+            .and_then(|mut file| Ok(file.read_to_end(&mut bytes)?))
+            .map_err(|error| {
+                to_ruby_err::<RuntimeError, _>(format!(
+                    "Cannot read `{}`: {}",
+                    guest_path.to_str(),
+                    error
+                ))
+            })?;
+
+        Ok(RString::from_bytes(&bytes, &Encoding::utf8()))
+    }
+
+    pub fn stdout(&self) -> RubyResult<RString> {
+        let mut state = self.inner.state();
+
+        read_captured(
+            state
+                .fs
+                .stdout_mut()
+                .map_err(to_ruby_err::<RuntimeError, _>)?,
+            "stdout",
+        )
+    }
+
+    pub fn stderr(&self) -> RubyResult<RString> {
+        let mut state = self.inner.state();
+
+        read_captured(
+            state
+                .fs
+                .stderr_mut()
+                .map_err(to_ruby_err::<RuntimeError, _>)?,
+            "stderr",
+        )
+    }
+}
+
+pub(crate) mod ruby_environment_extra {
+    use crate::{
+        error::unwrap_or_raise, import_object::ImportObject, keywords::Keywords, store::RubyStore,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Hash, Object,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn generate_import_object(
+        argc: Argc,
+        argv: *const AnyObject,
+        itself: super::RubyEnvironment,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let store = Value::from(0);
+            let wasi_version = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("2:").as_ptr(),
+                    &store,
+                    &wasi_version,
+                    &keywords,
+                )
+            };
+
+            let store = AnyObject::from(store).try_convert_to::<RubyStore>()?;
+            let wasi_version = super::to_wasi_version(&AnyObject::from(wasi_version))?;
+            let keywords = Keywords::new(AnyObject::from(keywords), &["extra"])?;
+            let extra = match keywords.get("extra") {
+                Some(extra) => Some(extra.try_convert_to::<Hash>()?),
+                None => None,
+            };
+
+            Ok(ImportObject::ruby_new(
+                itself.upcast().import_object_with_extra(
+                    store.upcast().inner(),
+                    wasi_version,
+                    extra.as_ref(),
+                )?,
+            ))
+        })
+    }
+}
+
+/// Reads, and removes, the bytes written to a captured standard
+/// stream.
+fn read_captured(
+    file: &mut Option<Box<dyn wasmer_wasi::VirtualFile>>,
+    stream_name: &str,
+) -> RubyResult<RString> {
+    let pipe = file
+        .as_mut()
+        .and_then(|file| file.downcast_mut::<wasmer_wasi::Pipe>())
+        .ok_or_else(|| {
+            to_ruby_err::<RuntimeError, _>(format!(
+                "The {0} stream is not captured, use `StateBuilder#capture_{0}`",
+                stream_name
+            ))
+        })?;
+
+    let mut bytes = Vec::new();
+    pipe.read_to_end(&mut bytes)
+        .map_err(to_ruby_err::<RuntimeError, _>)?;
+
+    Ok(RString::from_bytes(&bytes, &Encoding::utf8()))
+}
+
+/// A clock replacing the clocks of the host, to make the time
+/// deterministic.
+#[derive(Clone)]
+enum Clock {
+    /// All the clocks return this time, in nanoseconds.
+    Fixed(u64),
+    /// All the clocks call this Ruby block, with the name of the
+    /// clock.
+    Callback(AnyObject),
+}
+
+// The clock is only called by the guest running on the Ruby thread.
+unsafe impl Send for Clock {}
+unsafe impl Sync for Clock {}
+
+impl GcMark for Clock {
+    fn gc_mark(&self) {
+        if let Clock::Callback(callback) = self {
+            callback.gc_mark();
+        }
+    }
+}
+
+/// Reads a time in nanoseconds, given as an `Integer` or a `Time`.
+fn to_nanoseconds(time: &AnyObject) -> RubyResult<u64> {
+    if let Ok(time) = time.try_convert_to::<Integer>() {
+        return Ok(time.to_u64());
+    }
+
+    if time.respond_to("nsec") {
+        let seconds = unsafe { time.send("to_i", &[]) }.try_convert_to::<Integer>()?;
+        let nanoseconds = unsafe { time.send("nsec", &[]) }.try_convert_to::<Integer>()?;
+
+        return u64::try_from(seconds.to_i64())
+            .ok()
+            .and_then(|seconds| seconds.checked_mul(1_000_000_000))
+            .and_then(|time| time.checked_add(nanoseconds.to_u64()))
+            .ok_or_else(|| {
+                to_ruby_err::<ArgumentError, _>(format!(
+                    "The time `{}` cannot be represented as nanoseconds since the epoch in a `u64`",
+                    unsafe { time.send("inspect", &[]) }
+                        .try_convert_to::<RString>()
+                        .map(|inspect| inspect.to_string())
+                        .unwrap_or_default()
+                ))
+            });
+    }
+
+    Err(to_ruby_err::<TypeError, _>(format!(
+        "A time must be an `Integer` of nanoseconds or a `Time`, not a `{:?}`",
+        time.ty()
+    )))
+}
+
+/// Writes `bytes` at `offset` in the memory of the guest. Returns
+/// `false` if they don't fit in the memory.
+fn write_memory(memory: &wasmer::Memory, offset: usize, bytes: &[u8]) -> bool {
+    let view = memory.view::<u8>();
+
+    match view.get(offset..offset.saturating_add(bytes.len())) {
+        Some(cells) => {
+            for (cell, byte) in cells.iter().zip(bytes) {
+                cell.set(*byte);
+            }
+
+            true
+        }
+        None => false,
+    }
+}
+
+/// Creates the `clock_time_get(clock_id, precision, time_pointer) ->
+/// errno` WASI function, reading the time from `clock`.
+fn clock_time_get_function(
+    store: &wasmer::Store,
+    environment: &wasmer_wasi::WasiEnv,
+    clock: &Clock,
+) -> wasmer::Function {
+    let clock = clock.clone();
+
+    wasmer::Function::new_with_env(
+        store,
+        wasmer::FunctionType::new(
+            vec![wasmer::Type::I32, wasmer::Type::I64, wasmer::Type::I32],
+            vec![wasmer::Type::I32],
+        ),
+        environment.clone(),
+        move |environment: &wasmer_wasi::WasiEnv,
+              arguments: &[wasmer::Value]|
+              -> Result<Vec<wasmer::Value>, wasmer::RuntimeError> {
+            let clock_name = match arguments[0].unwrap_i32() as u32 {
+                wasmer_wasi::types::__WASI_CLOCK_REALTIME => "realtime",
+                wasmer_wasi::types::__WASI_CLOCK_MONOTONIC => "monotonic",
+                wasmer_wasi::types::__WASI_CLOCK_PROCESS_CPUTIME_ID => "process_cputime",
+                wasmer_wasi::types::__WASI_CLOCK_THREAD_CPUTIME_ID => "thread_cputime",
+                _ => {
+                    return Ok(vec![wasmer::Value::I32(
+                        wasmer_wasi::types::__WASI_EINVAL.into(),
+                    )])
+                }
+            };
+
+            let time = match &clock {
+                Clock::Fixed(time) => *time,
+                // An exception raised by the callback becomes a trap.
+                Clock::Callback(callback) => protect(|| unsafe {
+                    callback.send("call", &[Symbol::new(clock_name).to_any_object()])
+                })
+                .and_then(|time| to_nanoseconds(&time))
+                .map_err(|error| wasmer::RuntimeError::new(error.to_string()))?,
+            };
+
+            let errno = if write_memory(
+                environment.memory(),
+                arguments[2].unwrap_i32() as u32 as usize,
+                &time.to_le_bytes(),
+            ) {
+                wasmer_wasi::types::__WASI_ESUCCESS
+            } else {
+                wasmer_wasi::types::__WASI_EFAULT
+            };
+
+            Ok(vec![wasmer::Value::I32(errno.into())])
+        },
+    )
+}
+
+/// A small seedable pseudo-random number generator (SplitMix64). It
+/// is not cryptographically secure, but it makes runs reproducible.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+    }
+}
+
+/// Creates the `random_get(buffer, buffer_length) -> errno` WASI
+/// function, filling the buffer with pseudo-random bytes generated
+/// from `seed`.
+fn random_get_function(
+    store: &wasmer::Store,
+    environment: &wasmer_wasi::WasiEnv,
+    seed: u64,
+) -> wasmer::Function {
+    let generator = Arc::new(Mutex::new(SplitMix64(seed)));
+
+    wasmer::Function::new_with_env(
+        store,
+        wasmer::FunctionType::new(
+            vec![wasmer::Type::I32, wasmer::Type::I32],
+            vec![wasmer::Type::I32],
+        ),
+        environment.clone(),
+        move |environment: &wasmer_wasi::WasiEnv,
+              arguments: &[wasmer::Value]|
+              -> Result<Vec<wasmer::Value>, wasmer::RuntimeError> {
+            let length = arguments[1].unwrap_i32() as u32 as usize;
+            let mut generator = generator.lock().unwrap();
+            let bytes = (0..(length + 7) / 8)
+                .flat_map(|_| generator.next().to_le_bytes().to_vec())
+                .take(length)
+                .collect::<Vec<u8>>();
+
+            let errno = if write_memory(
+                environment.memory(),
+                arguments[0].unwrap_i32() as u32 as usize,
+                &bytes,
+            ) {
+                wasmer_wasi::types::__WASI_ESUCCESS
+            } else {
+                wasmer_wasi::types::__WASI_EFAULT
+            };
+
+            Ok(vec![wasmer::Value::I32(errno.into())])
+        },
+    )
+}
+
+/// Generates the WASI functions of an environment: the ones of
+/// `wasmer_wasi`, where the clock and the random numbers are replaced
+/// if they are deterministic.
+#[derive(Clone)]
+struct WasiFunctions {
+    version: wasmer_wasi::WasiVersion,
+    clock: Option<Clock>,
+    random_seed: Option<u64>,
+}
+
+impl WasiFunctions {
+    fn namespace_name(&self) -> &'static str {
+        match self.version {
+            wasmer_wasi::WasiVersion::Snapshot0 => "wasi_unstable",
+            _ => "wasi_snapshot_preview1",
+        }
+    }
+
+    /// Generates the WASI functions, over `environment`.
+    fn namespace(
         &self,
-        store: &Store,
-        wasi_version: &Integer,
-    ) -> RubyResult<AnyObject> {
-        let import_object = wasmer_wasi::generate_import_object_from_env(
-            store.inner(),
-            self.inner.clone(),
-            Version::try_from(wasi_version)
-                .map_err(to_ruby_err::<TypeError, _>)?
-                .into(),
-        );
+        store: &wasmer::Store,
+        environment: &wasmer_wasi::WasiEnv,
+    ) -> wasmer::Exports {
+        let mut namespace =
+            wasmer_wasi::generate_import_object_from_env(store, environment.clone(), self.version)
+                .get_namespace_exports(self.namespace_name())
+                .unwrap_or_else(wasmer::Exports::new);
+
+        if let Some(clock) = &self.clock {
+            namespace.insert(
+                "clock_time_get",
+                clock_time_get_function(store, environment, clock),
+            );
+        }
+
+        if let Some(seed) = self.random_seed {
+            namespace.insert("random_get", random_get_function(store, environment, seed));
+        }
 
-        Ok(ImportObject::ruby_new(ImportObject::raw_new(import_object)))
+        namespace
+    }
+}
+
+/// The receiver of the traced WASI calls.
+#[derive(Clone)]
+enum Tracer {
+    /// A Ruby block, called with the name of the syscall, its
+    /// decoded arguments and its errno.
+    Callback(AnyObject),
+    /// A Ruby `IO`, receiving one line per syscall.
+    Io(AnyObject),
+}
+
+// The tracer is only used by the guest running on the Ruby thread.
+unsafe impl Send for Tracer {}
+unsafe impl Sync for Tracer {}
+
+impl GcMark for Tracer {
+    fn gc_mark(&self) {
+        match self {
+            Tracer::Callback(object) | Tracer::Io(object) => object.gc_mark(),
+        }
+    }
+}
+
+impl Tracer {
+    /// Traces a syscall. An exception raised by the tracer is caught,
+    /// and returned as a trap.
+    fn trace(
+        &self,
+        syscall: &str,
+        arguments: &[(String, TracedValue)],
+        errno: Option<u16>,
+    ) -> Result<(), wasmer::RuntimeError> {
+        let result = match self {
+            Tracer::Callback(callback) => {
+                let mut ruby_arguments = Hash::new();
+
+                for (name, value) in arguments {
+                    ruby_arguments.store(Symbol::new(name), value.to_ruby_object());
+                }
+
+                let ruby_arguments = [
+                    Symbol::new(syscall).to_any_object(),
+                    ruby_arguments.to_any_object(),
+                    errno.map_or_else(
+                        || NilClass::new().to_any_object(),
+                        |errno| Integer::new(errno.into()).to_any_object(),
+                    ),
+                ];
+
+                protect(|| unsafe { callback.send("call", &ruby_arguments) })
+            }
+
+            Tracer::Io(io) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let result = match errno {
+                    Some(errno) => match ERRNO_NAMES.get(errno as usize) {
+                        Some(name) if errno != 0 => format!("{} ({})", errno, name),
+                        _ => errno.to_string(),
+                    },
+                    None => "?".to_string(),
+                };
+
+                let line = RString::new_utf8(&format!("{}({}) = {}\n", syscall, arguments, result))
+                    .to_any_object();
+
+                protect(|| unsafe { io.send("write", &[line.clone()]) })
+            }
+        };
+
+        result
+            .map(|_| ())
+            .map_err(|error| wasmer::RuntimeError::new(error.to_string()))
+    }
+}
+
+/// The names of the WASI errnos, indexed by their value.
+const ERRNO_NAMES: &[&str] = &[
+    "ESUCCESS",
+    "E2BIG",
+    "EACCES",
+    "EADDRINUSE",
+    "EADDRNOTAVAIL",
+    "EAFNOSUPPORT",
+    "EAGAIN",
+    "EALREADY",
+    "EBADF",
+    "EBADMSG",
+    "EBUSY",
+    "ECANCELED",
+    "ECHILD",
+    "ECONNABORTED",
+    "ECONNREFUSED",
+    "ECONNRESET",
+    "EDEADLK",
+    "EDESTADDRREQ",
+    "EDOM",
+    "EDQUOT",
+    "EEXIST",
+    "EFAULT",
+    "EFBIG",
+    "EHOSTUNREACH",
+    "EIDRM",
+    "EILSEQ",
+    "EINPROGRESS",
+    "EINTR",
+    "EINVAL",
+    "EIO",
+    "EISCONN",
+    "EISDIR",
+    "ELOOP",
+    "EMFILE",
+    "EMLINK",
+    "EMSGSIZE",
+    "EMULTIHOP",
+    "ENAMETOOLONG",
+    "ENETDOWN",
+    "ENETRESET",
+    "ENETUNREACH",
+    "ENFILE",
+    "ENOBUFS",
+    "ENODEV",
+    "ENOENT",
+    "ENOEXEC",
+    "ENOLCK",
+    "ENOLINK",
+    "ENOMEM",
+    "ENOMSG",
+    "ENOPROTOOPT",
+    "ENOSPC",
+    "ENOSYS",
+    "ENOTCONN",
+    "ENOTDIR",
+    "ENOTEMPTY",
+    "ENOTRECOVERABLE",
+    "ENOTSOCK",
+    "ENOTSUP",
+    "ENOTTY",
+    "ENXIO",
+    "EOVERFLOW",
+    "EOWNERDEAD",
+    "EPERM",
+    "EPIPE",
+    "EPROTO",
+    "EPROTONOSUPPORT",
+    "EPROTOTYPE",
+    "ERANGE",
+    "EROFS",
+    "ESPIPE",
+    "ESRCH",
+    "ESTALE",
+    "ETIMEDOUT",
+    "ETXTBSY",
+    "EXDEV",
+    "ENOTCAPABLE",
+];
+
+/// A decoded argument of a traced WASI call.
+enum TracedValue {
+    Integer(i64),
+    String(String),
+    /// The argument couldn't be decoded, e.g. a pointer out of the
+    /// memory, or an output of a failed call.
+    Unknown,
+}
+
+impl TracedValue {
+    fn to_ruby_object(&self) -> AnyObject {
+        match self {
+            TracedValue::Integer(integer) => Integer::new(*integer).to_any_object(),
+            TracedValue::String(string) => RString::new_utf8(string).to_any_object(),
+            TracedValue::Unknown => NilClass::new().to_any_object(),
+        }
+    }
+}
+
+impl fmt::Display for TracedValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TracedValue::Integer(integer) => write!(formatter, "{}", integer),
+            TracedValue::String(string) => write!(formatter, "{:?}", string),
+            TracedValue::Unknown => write!(formatter, "?"),
+        }
     }
 }
 
-#[rubyfunction]
-pub fn get_version(module: &Module, strict: &Boolean) -> RubyResult<AnyObject> {
-    Ok(
-        wasmer_wasi::get_wasi_version(&module.inner(), strict.to_bool())
-            .map(|version| Version::from(&version).to_integer().to_any_object())
-            .unwrap_or_else(|| NilClass::new().to_any_object()),
+/// How a parameter of a WASI function is decoded.
+#[derive(Clone, Copy)]
+enum TracedParameter {
+    /// An integer, e.g. a file descriptor, flags, or a pointer to a
+    /// structure.
+    Value(&'static str),
+    /// A pointer and a length (2 parameters), read as a string.
+    Path(&'static str),
+    /// A pointer to I/O vectors and their number (2 parameters), read
+    /// as the total number of bytes.
+    Iovecs(&'static str),
+    /// A pointer to a 32-bit integer written by the call.
+    Output32(&'static str),
+    /// A pointer to a 64-bit integer written by the call.
+    Output64(&'static str),
+}
+
+/// Returns how the parameters of the WASI function `syscall` are
+/// decoded, or `None` if it is unknown.
+fn traced_parameters(syscall: &str) -> Option<&'static [TracedParameter]> {
+    use TracedParameter::*;
+
+    let parameters: &'static [TracedParameter] = match syscall {
+        "args_get" => &[Value("argv"), Value("argv_buf")],
+        "args_sizes_get" => &[Output32("argc"), Output32("argv_buf_size")],
+        "environ_get" => &[Value("environ"), Value("environ_buf")],
+        "environ_sizes_get" => &[Output32("environc"), Output32("environ_buf_size")],
+        "clock_res_get" => &[Value("clock_id"), Output64("resolution")],
+        "clock_time_get" => &[Value("clock_id"), Value("precision"), Output64("time")],
+        "fd_advise" => &[Value("fd"), Value("offset"), Value("len"), Value("advice")],
+        "fd_allocate" => &[Value("fd"), Value("offset"), Value("len")],
+        "fd_close" | "fd_datasync" | "fd_sync" => &[Value("fd")],
+        "fd_fdstat_get" | "fd_filestat_get" | "fd_prestat_get" => &[Value("fd"), Value("buf")],
+        "fd_fdstat_set_flags" => &[Value("fd"), Value("flags")],
+        "fd_fdstat_set_rights" => &[
+            Value("fd"),
+            Value("fs_rights_base"),
+            Value("fs_rights_inheriting"),
+        ],
+        "fd_filestat_set_size" => &[Value("fd"), Value("size")],
+        "fd_filestat_set_times" => &[
+            Value("fd"),
+            Value("atim"),
+            Value("mtim"),
+            Value("fst_flags"),
+        ],
+        "fd_pread" => &[
+            Value("fd"),
+            Iovecs("iovs"),
+            Value("offset"),
+            Output32("nread"),
+        ],
+        "fd_pwrite" => &[
+            Value("fd"),
+            Iovecs("iovs"),
+            Value("offset"),
+            Output32("nwritten"),
+        ],
+        "fd_read" => &[Value("fd"), Iovecs("iovs"), Output32("nread")],
+        "fd_write" => &[Value("fd"), Iovecs("iovs"), Output32("nwritten")],
+        "fd_prestat_dir_name" => &[Value("fd"), Value("path"), Value("path_len")],
+        "fd_readdir" => &[
+            Value("fd"),
+            Value("buf"),
+            Value("buf_len"),
+            Value("cookie"),
+            Output32("bufused"),
+        ],
+        "fd_renumber" => &[Value("fd"), Value("to")],
+        "fd_seek" => &[
+            Value("fd"),
+            Value("offset"),
+            Value("whence"),
+            Output64("newoffset"),
+        ],
+        "fd_tell" => &[Value("fd"), Output64("offset")],
+        "path_create_directory" | "path_remove_directory" | "path_unlink_file" => {
+            &[Value("fd"), Path("path")]
+        }
+        "path_filestat_get" => &[Value("fd"), Value("flags"), Path("path"), Value("buf")],
+        "path_filestat_set_times" => &[
+            Value("fd"),
+            Value("flags"),
+            Path("path"),
+            Value("atim"),
+            Value("mtim"),
+            Value("fst_flags"),
+        ],
+        "path_link" => &[
+            Value("old_fd"),
+            Value("old_flags"),
+            Path("old_path"),
+            Value("new_fd"),
+            Path("new_path"),
+        ],
+        "path_open" => &[
+            Value("fd"),
+            Value("dirflags"),
+            Path("path"),
+            Value("oflags"),
+            Value("fs_rights_base"),
+            Value("fs_rights_inheriting"),
+            Value("fdflags"),
+            Output32("opened_fd"),
+        ],
+        "path_readlink" => &[
+            Value("fd"),
+            Path("path"),
+            Value("buf"),
+            Value("buf_len"),
+            Output32("bufused"),
+        ],
+        "path_rename" => &[
+            Value("old_fd"),
+            Path("old_path"),
+            Value("new_fd"),
+            Path("new_path"),
+        ],
+        "path_symlink" => &[Path("old_path"), Value("fd"), Path("new_path")],
+        "poll_oneoff" => &[
+            Value("in"),
+            Value("out"),
+            Value("nsubscriptions"),
+            Output32("nevents"),
+        ],
+        "proc_exit" => &[Value("rval")],
+        "proc_raise" => &[Value("sig")],
+        "sched_yield" => &[],
+        "random_get" => &[Value("buf"), Value("buf_len")],
+        "sock_recv" => &[
+            Value("fd"),
+            Iovecs("ri_data"),
+            Value("ri_flags"),
+            Output32("ro_datalen"),
+            Value("ro_flags"),
+        ],
+        "sock_send" => &[
+            Value("fd"),
+            Iovecs("si_data"),
+            Value("si_flags"),
+            Output32("so_datalen"),
+        ],
+        "sock_shutdown" => &[Value("fd"), Value("how")],
+        _ => return None,
+    };
+
+    Some(parameters)
+}
+
+/// Reads `length` bytes at `offset` in the memory of the guest, or
+/// `None` if they don't fit in the memory.
+fn read_memory(memory: &wasmer::Memory, offset: usize, length: usize) -> Option<Vec<u8>> {
+    memory
+        .view::<u8>()
+        .get(offset..offset.checked_add(length)?)
+        .map(|cells| cells.iter().map(|cell| cell.get()).collect())
+}
+
+fn read_u32(memory: &wasmer::Memory, offset: usize) -> Option<u32> {
+    let bytes = read_memory(memory, offset, 4)?;
+
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(memory: &wasmer::Memory, offset: usize) -> Option<u64> {
+    let bytes = read_memory(memory, offset, 8)?;
+    let mut array = [0; 8];
+    array.copy_from_slice(&bytes);
+
+    Some(u64::from_le_bytes(array))
+}
+
+/// Reads an integer argument. The 32-bit integers are unsigned, as
+/// they are pointers, lengths or file descriptors.
+fn to_traced_integer(value: &wasmer::Value) -> i64 {
+    match value {
+        wasmer::Value::I32(value) => *value as u32 as i64,
+        wasmer::Value::I64(value) => *value,
+        _ => 0,
+    }
+}
+
+/// The WASI functions called by the traced functions of an import
+/// object. They are generated once per instance, over the initialized
+/// environment, and identified by the memory of the instance.
+type WasiNamespace = Arc<Mutex<Option<(wasmer::Memory, wasmer::Exports)>>>;
+
+/// The environment of a traced WASI function.
+#[derive(Clone)]
+struct TracedEnvironment {
+    wasi_env: wasmer_wasi::WasiEnv,
+    functions: WasiFunctions,
+    /// Shared by all the traced functions of an import object.
+    wasi_namespace: WasiNamespace,
+    syscall: String,
+    tracer: Tracer,
+    /// The traced WASI function, created once the memory of
+    /// `wasi_env` is initialized.
+    function: Option<wasmer::Function>,
+}
+
+impl wasmer::WasmerEnv for TracedEnvironment {
+    fn init_with_instance(
+        &mut self,
+        instance: &wasmer::Instance,
+    ) -> Result<(), wasmer::HostEnvInitError> {
+        wasmer::WasmerEnv::init_with_instance(&mut self.wasi_env, instance)?;
+
+        // The WASI functions are created over the initialized
+        // environment, so that they see the memory of the instance,
+        // by the first traced function initialized with this
+        // instance.
+        let memory = self.wasi_env.memory();
+        let mut wasi_namespace = self
+            .wasi_namespace
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        let is_outdated = match &*wasi_namespace {
+            Some((namespace_memory, _)) => !namespace_memory.same(memory),
+            None => true,
+        };
+
+        if is_outdated {
+            *wasi_namespace = Some((
+                memory.clone(),
+                self.functions.namespace(instance.store(), &self.wasi_env),
+            ));
+        }
+
+        self.function = wasi_namespace
+            .as_ref()
+            .and_then(|(_, namespace)| namespace.get_function(&self.syscall).ok())
+            .cloned();
+
+        Ok(())
+    }
+}
+
+/// Creates a function of type `function_type` calling the WASI
+/// function of `environment`, and reporting each call, with its
+/// decoded arguments and its errno, to the tracer.
+fn traced_function(
+    store: &wasmer::Store,
+    environment: TracedEnvironment,
+    function_type: &wasmer::FunctionType,
+) -> wasmer::Function {
+    wasmer::Function::new_with_env(
+        store,
+        function_type.clone(),
+        environment,
+        |environment: &TracedEnvironment,
+         arguments: &[wasmer::Value]|
+         -> Result<Vec<wasmer::Value>, wasmer::RuntimeError> {
+            let syscall = &environment.syscall;
+            let tracer = &environment.tracer;
+            let memory = environment.wasi_env.memory();
+            let function = environment.function.as_ref().ok_or_else(|| {
+                wasmer::RuntimeError::new(format!(
+                    "The WASI function `{}` is not initialized",
+                    syscall
+                ))
+            })?;
+            let parameters = traced_parameters(syscall);
+
+            // Decode the inputs before the call, as it may change the
+            // memory.
+            let mut traced_arguments = Vec::new();
+            let mut outputs = Vec::new();
+
+            match parameters {
+                Some(parameters) => {
+                    let mut arguments = arguments.iter().map(to_traced_integer);
+
+                    for parameter in parameters {
+                        let mut next = || arguments.next().unwrap_or_default() as usize;
+
+                        let (name, value) = match *parameter {
+                            TracedParameter::Value(name) => {
+                                (name, TracedValue::Integer(next() as i64))
+                            }
+                            TracedParameter::Path(name) => {
+                                let (pointer, length) = (next(), next());
+
+                                (
+                                    name,
+                                    read_memory(memory, pointer, length).map_or(
+                                        TracedValue::Unknown,
+                                        |bytes| {
+                                            TracedValue::String(
+                                                String::from_utf8_lossy(&bytes).into_owned(),
+                                            )
+                                        },
+                                    ),
+                                )
+                            }
+                            TracedParameter::Iovecs(name) => {
+                                let (pointer, length) = (next(), next());
+
+                                // Each I/O vector is a pointer and a
+                                // length, on 32 bits each.
+                                (
+                                    name,
+                                    (0..length)
+                                        .map(|nth| {
+                                            read_u32(memory, pointer + nth * 8 + 4).map(u64::from)
+                                        })
+                                        .sum::<Option<u64>>()
+                                        .map_or(TracedValue::Unknown, |bytes| {
+                                            TracedValue::Integer(bytes as i64)
+                                        }),
+                                )
+                            }
+                            TracedParameter::Output32(name) | TracedParameter::Output64(name) => {
+                                outputs.push((traced_arguments.len(), *parameter, next()));
+
+                                (name, TracedValue::Unknown)
+                            }
+                        };
+
+                        traced_arguments.push((name.to_string(), value));
+                    }
+                }
+
+                None => {
+                    for (nth, argument) in arguments.iter().enumerate() {
+                        traced_arguments.push((
+                            format!("arg{}", nth),
+                            TracedValue::Integer(to_traced_integer(argument)),
+                        ));
+                    }
+                }
+            }
+
+            let results = match function.call(arguments) {
+                Ok(results) => results,
+                Err(error) => {
+                    // The call didn't return, e.g. `proc_exit`.
+                    tracer.trace(syscall, &traced_arguments, None)?;
+
+                    return Err(error);
+                }
+            };
+
+            let errno = results.first().and_then(|result| match result {
+                wasmer::Value::I32(errno) => Some(*errno as u16),
+                _ => None,
+            });
+
+            // The outputs are meaningful only if the call succeeded.
+            if errno == Some(wasmer_wasi::types::__WASI_ESUCCESS) {
+                for (position, parameter, pointer) in outputs {
+                    let output = match parameter {
+                        TracedParameter::Output32(_) => read_u32(memory, pointer).map(i64::from),
+                        _ => read_u64(memory, pointer).map(|output| output as i64),
+                    };
+
+                    if let Some(output) = output {
+                        traced_arguments[position].1 = TracedValue::Integer(output);
+                    }
+                }
+            }
+
+            tracer.trace(syscall, &traced_arguments, errno)?;
+
+            Ok(results.into_vec())
+        },
     )
 }
+
+pub(crate) mod ruby_wasi_extra {
+    use super::Version;
+    use crate::{
+        error::{to_ruby_err, unwrap_or_raise, ArgumentError},
+        keywords::Keywords,
+        module::RubyModule,
+    };
+    use rutie::{
+        rubysys::class,
+        types::{Argc, Value},
+        util::str_to_cstring,
+        AnyObject, Boolean, NilClass, Object, Symbol,
+    };
+    use rutie_derive::UpcastRubyClass;
+
+    #[allow(improper_ctypes_definitions)] // No choice, that's how `rutie` is designed.
+    pub extern "C" fn get_version(
+        argc: Argc,
+        argv: *const AnyObject,
+        _module: AnyObject,
+    ) -> AnyObject {
+        unwrap_or_raise(|| {
+            let module = Value::from(0);
+            let strict = Value::from(0);
+            let keywords = Value::from(0);
+
+            unsafe {
+                let argv_pointer = argv as *const Value;
+
+                class::rb_scan_args(
+                    argc,
+                    argv_pointer,
+                    str_to_cstring("2:").as_ptr(),
+                    &module,
+                    &strict,
+                    &keywords,
+                )
+            };
+
+            let module = AnyObject::from(module).try_convert_to::<RubyModule>()?;
+            let strict = AnyObject::from(strict).try_convert_to::<Boolean>()?;
+            let keywords = Keywords::new(AnyObject::from(keywords), &["as"])?;
+
+            let as_symbol = match keywords.get("as") {
+                Some(kind) => match kind.try_convert_to::<Symbol>()?.to_str() {
+                    "symbol" => true,
+                    "integer" => false,
+                    _ => {
+                        return Err(to_ruby_err::<ArgumentError, _>(
+                            "The `as` keyword must be `:integer` or `:symbol`",
+                        ))
+                    }
+                },
+                None => false,
+            };
+
+            Ok(
+                wasmer_wasi::get_wasi_version(module.upcast().inner(), strict.to_bool())
+                    .map(|version| {
+                        let version = Version::from(&version);
+
+                        if as_symbol {
+                            version.to_symbol().to_any_object()
+                        } else {
+                            version.to_integer().to_any_object()
+                        }
+                    })
+                    .unwrap_or_else(|| NilClass::new().to_any_object()),
+            )
+        })
+    }
+}
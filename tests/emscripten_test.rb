@@ -0,0 +1,58 @@
+require "prelude"
+
+class EmscriptenTest < Minitest::Test
+  def emscripten_module(store = Store.new)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "env" "memory" (memory 256 256))
+        (import "env" "table" (table 0 funcref))
+        (import "env" "_emscripten_memcpy_big" (func (param i32 i32 i32) (result i32))))
+      WAST
+    )
+  end
+
+  def test_detect
+    assert Emscripten.detect?(emscripten_module)
+    refute Emscripten.detect?(Module.new(Store.new, "(module)"))
+  end
+
+  def test_generate_import_object
+    store = Store.new
+    module_ = emscripten_module store
+
+    import_object = Emscripten.generate_import_object store, module_
+
+    assert_kind_of ImportObject, import_object
+    assert import_object.contains_namespace?("env")
+    assert_equal import_object.validate_for(module_), []
+    assert_kind_of Instance, Instance.new(module_, import_object)
+  end
+
+  def test_run
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (import "env" "memory" (memory 256 256))
+        (import "env" "table" (table 0 funcref))
+        (import "env" "_emscripten_memcpy_big" (func $memcpy (param i32 i32 i32) (result i32)))
+        (data (i32.const 1024) "Hello")
+        (func (export "run")
+          i32.const 2048
+          i32.const 1024
+          i32.const 5
+          call $memcpy
+          drop)
+        (export "memory" (memory 0)))
+      WAST
+    )
+
+    instance = Emscripten.run module_, [], entrypoint: "run"
+    view = instance.exports.memory.uint8_view(2048)
+
+    assert_kind_of Instance, instance
+    assert_equal (0...5).map { |nth| view[nth].chr }.join, "Hello"
+  end
+end
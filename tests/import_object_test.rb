@@ -113,4 +113,253 @@ class ImportObjectTest < Minitest::Test
     instance.exports.write_g.(11)
     assert_equal global.value, 11
   end
+
+  def globals_import_object(store, names)
+    import_object = ImportObject.new
+    import_object.register(
+      "env",
+      names.map { |name| [name, Global.new(store, Value.i32(7), false)] }.to_h
+    )
+
+    import_object
+  end
+
+  def test_namespaces_and_getters
+    store = Store.new
+    import_object = globals_import_object store, [:foo, :bar]
+    import_object.register :math, {}
+
+    assert_equal import_object.namespaces.sort, ["env", "math"]
+    assert import_object.contains_namespace?(:math)
+    assert_equal import_object["env"].keys.sort, ["bar", "foo"]
+    assert_equal import_object[:math], {}
+    assert_nil import_object["missing"]
+    assert_equal import_object.get("env", :foo), import_object["env"]["foo"]
+    assert_nil import_object.get("env", "missing")
+    assert_nil import_object.get("missing", "foo")
+    assert_equal import_object.to_h.keys.sort, ["env", "math"]
+    assert_equal import_object.to_h["env"], import_object["env"]
+  end
+
+  def test_unregister
+    import_object = globals_import_object Store.new, [:foo]
+
+    namespace = import_object.unregister "env"
+
+    assert_equal namespace.keys, ["foo"]
+    assert_equal import_object.contains_namespace?("env"), false
+    assert_nil import_object.unregister("env")
+  end
+
+  def test_merge
+    store = Store.new
+    left = globals_import_object store, [:foo, :bar]
+    right = globals_import_object store, [:bar]
+    right.register "math", {}
+
+    merged = left.merge right
+
+    assert_equal merged.namespaces.sort, ["env", "math"]
+    assert_equal merged.get("env", "foo"), left.get("env", "foo")
+    assert_equal merged.get("env", "bar"), right.get("env", "bar")
+    refute_equal merged.get("env", "bar"), left.get("env", "bar")
+    assert_equal left.namespaces, ["env"]
+  end
+
+  def test_validate_for
+    store = Store.new
+    module_ = Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "env" "foo" (global i32))
+        (import "env" "bar" (func))
+        (import "math" "sum" (func (param i32 i32) (result i32))))
+      WAST
+    )
+    import_object = globals_import_object store, [:foo, :bar]
+
+    errors = import_object.validate_for module_
+
+    assert_equal errors.map { |error| [error.module, error.name] }, [["env", "bar"], ["math", "sum"]]
+    assert_kind_of LinkError, errors[0]
+    assert_kind_of FunctionType, errors[0].expected
+    assert_kind_of GlobalType, errors[0].provided
+    assert_nil errors[1].provided
+
+    import_object.register "env", { :foo => Global.new(store, Value.i32(1), false), :bar => Function.new(store, proc { }, FunctionType.new([], [])) }
+    import_object.register "math", { :sum => Function.new(store, proc { |x, y| x + y }, FunctionType.new([Type::I32, Type::I32], [Type::I32])) }
+
+    assert_equal import_object.validate_for(module_), []
+  end
+
+  def resolved_module(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "env" "static" (func $static (result i32)))
+        (import "env" "dynamic" (func $dynamic (param i32) (result i32)))
+        (import "env" "memory" (memory 1))
+        (func (export "run") (param i32) (result i32)
+          call $static
+          local.get 0
+          call $dynamic
+          i32.add))
+      WAST
+    )
+  end
+
+  def test_resolver
+    store = Store.new
+    calls = []
+
+    import_object = ImportObject.new do |namespace, name, type|
+      calls << [namespace, name, type.class]
+
+      case type
+      when FunctionType then Function.new(store, ->(x) { x * 2 }, type)
+      when MemoryType then Memory.new(store, type)
+      end
+    end
+    import_object.register "env", { :static => Function.new(store, -> { 1 }, FunctionType.new([], [Type::I32])) }
+
+    instance = Instance.new resolved_module(store), import_object
+
+    assert_equal instance.exports.run.(20), 41
+    assert_equal calls, [["env", "dynamic", FunctionType], ["env", "memory", MemoryType]]
+  end
+
+  def test_resolver_returns_nil
+    import_object = ImportObject.new { |namespace, name, type| nil }
+
+    error = assert_raises(LinkError) {
+      Instance.new resolved_module(Store.new), import_object
+    }
+
+    assert_equal error.name, "static"
+  end
+
+  def test_resolver_returns_invalid_type
+    import_object = ImportObject.new { |namespace, name, type| 42 }
+
+    assert_raises(TypeError) {
+      Instance.new resolved_module(Store.new), import_object
+    }
+  end
+
+  def test_resolver_raises
+    import_object = ImportObject.new { |namespace, name, type| raise KeyError, "no #{name}" }
+
+    error = assert_raises(KeyError) {
+      Instance.new resolved_module(Store.new), import_object
+    }
+
+    assert_equal error.message, "no static"
+  end
+
+  def test_register_exports
+    store = Store.new
+    math = Instance.new(
+      Module.new(
+        store,
+        (<<~WAST)
+        (module
+          (func (export "sum") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add))
+        WAST
+      ),
+      nil
+    )
+
+    import_object = ImportObject.new
+    import_object.register "math", math.exports
+
+    assert_equal import_object.get("math", "sum"), math.exports.sum
+
+    module_ = Module.new store, '(module (import "math" "sum" (func (param i32 i32) (result i32))) (export "sum" (func 0)))'
+    instance = Instance.new module_, import_object
+
+    assert_equal instance.exports.sum.(1, 2), 3
+  end
+
+  def test_register_invalid_namespace
+    assert_raises(TypeError) {
+      ImportObject.new.register "math", 42
+    }
+  end
+
+  def stubbed_module
+    Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (import "env" "log" (func $log (param i32 f64) (result i64)))
+        (import "env" "memory" (memory 1 2))
+        (import "env" "counter" (global $counter (mut i32)))
+        (import "env" "table" (table 2 funcref))
+        (func (export "run") (result i64)
+          i32.const 7
+          f64.const 1.5
+          call $log)
+        (func (export "counter") (result i32)
+          global.get $counter))
+      WAST
+    )
+  end
+
+  def test_stubs_for_trap
+    module_ = stubbed_module
+    import_object = ImportObject.stubs_for module_
+
+    assert_equal import_object.namespaces, ["env"]
+    assert_equal import_object["env"].keys.sort, ["counter", "log", "memory", "table"]
+    assert_equal import_object.validate_for(module_), []
+
+    instance = Instance.new module_, import_object
+
+    assert_equal instance.exports.counter.(), 0
+    assert_raises(RuntimeError) {
+      instance.exports.run.()
+    }
+    assert_equal import_object.calls, []
+  end
+
+  def test_stubs_for_zero
+    module_ = stubbed_module
+    instance = Instance.new module_, ImportObject.stubs_for(module_, mode: :zero)
+
+    assert_equal instance.exports.run.(), 0
+  end
+
+  def test_stubs_for_log
+    module_ = stubbed_module
+    import_object = ImportObject.stubs_for module_, mode: :log
+    instance = Instance.new module_, import_object
+
+    instance.exports.run.()
+    instance.exports.run.()
+
+    assert_equal import_object.calls, [["env", "log", [7, 1.5]], ["env", "log", [7, 1.5]]]
+  end
+
+  def test_stubs_for_missing_imports_only
+    store = Store.new
+    module_ = Module.new store, '(module (import "env" "f" (func (result i32))) (import "env" "g" (func)) (export "f" (func 0)))'
+
+    real = ImportObject.new
+    real.register "env", { :f => Function.new(store, -> { 42 }, FunctionType.new([], [Type::I32])) }
+
+    instance = Instance.new module_, ImportObject.stubs_for(module_).merge(real)
+
+    assert_equal instance.exports.f.(), 42
+  end
+
+  def test_stubs_for_invalid_mode
+    assert_raises(ArgumentError) {
+      ImportObject.stubs_for stubbed_module, mode: :foo
+    }
+  end
 end
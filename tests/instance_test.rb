@@ -64,4 +64,400 @@ class InstanceTest < Minitest::Test
       exports.foo
     }
   end
+
+  def all_kind_exports
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (func (export "my-func") (param i32 i64))
+        (global (export "__heap_base") i32 (i32.const 7))
+        (table (export "tab") 0 funcref)
+        (memory (export "memory") 1))
+      WAST
+    )
+
+    Instance.new(module_, nil).exports
+  end
+
+  def test_exports_get
+    exports = all_kind_exports
+
+    assert_kind_of Function, exports["my-func"]
+    assert_kind_of Global, exports[:__heap_base]
+    assert_equal exports["__heap_base"].value, 7
+    assert_nil exports["foo"]
+
+    assert_raises(TypeError) {
+      exports[42]
+    }
+  end
+
+  def test_exports_each
+    exports = all_kind_exports
+
+    refute_kind_of Enumerable, exports
+    assert_equal exports.each.map { |name, _| name }, ["my-func", "__heap_base", "tab", "memory"]
+    assert_equal exports.keys, ["my-func", "__heap_base", "tab", "memory"]
+
+    name, _ = exports.each.find { |_, extern| extern.is_a? Table }
+    assert_equal name, "tab"
+
+    enumerator = exports.each
+    assert_kind_of Enumerator, enumerator
+    assert_equal enumerator.with_index.map { |(name, _), index| [name, index] }.first, ["my-func", 0]
+    assert_same exports.each { |_| }, exports
+  end
+
+  def test_exports_to_h
+    hash = all_kind_exports.to_h
+
+    assert_equal hash.keys, ["my-func", "__heap_base", "tab", "memory"]
+    assert_kind_of Memory, hash["memory"]
+  end
+
+  def test_exports_typed_accessors
+    exports = all_kind_exports
+
+    assert_kind_of Function, exports.function("my-func")
+    assert_kind_of Global, exports.global(:__heap_base)
+    assert_kind_of Table, exports.table("tab")
+    assert_kind_of Memory, exports.memory("memory")
+
+    # Without argument, the export named like the accessor is returned.
+    assert_kind_of Memory, exports.memory
+  end
+
+  def test_exports_typed_accessors_kind_mismatch
+    exports = all_kind_exports
+
+    assert_raises(TypeError) {
+      exports.memory("my-func")
+    }
+
+    assert_raises(TypeError) {
+      exports.function("memory")
+    }
+
+    assert_raises(NameError) {
+      exports.function("foo")
+    }
+  end
+
+  def test_exports_are_cached
+    exports = all_kind_exports
+
+    assert exports.memory.equal? exports.memory
+    assert exports["my-func"].equal? exports.function("my-func")
+    assert exports.to_h["tab"].equal? exports["tab"]
+    assert exports.each.map { |_, extern| extern }[1].equal? exports[:__heap_base]
+  end
+
+  def test_exports_equality
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (memory $m 1)
+        (export "memory" (memory $m))
+        (export "alias" (memory $m))
+        (global (export "glob") i32 (i32.const 7)))
+      WAST
+    )
+    exports = Instance.new(module_, nil).exports
+
+    assert_equal exports.memory, exports.alias
+    refute exports.memory.equal? exports.alias
+    assert_equal exports.memory.hash, exports.alias.hash
+    assert exports.memory.eql? exports.alias
+    refute_equal exports.memory, exports.glob
+    assert_equal({ exports.memory => 1 }[exports.alias], 1)
+  end
+
+  def test_link_error_unknown_import
+    module_ = Module.new Store.new, '(module (import "env" "log" (func (param i32))))'
+
+    error = assert_raises(LinkError) {
+      Instance.new module_, nil
+    }
+
+    assert_kind_of InstantiationError, error
+    assert_kind_of RuntimeError, error
+    assert_equal error.module, "env"
+    assert_equal error.name, "log"
+    assert_kind_of FunctionType, error.expected
+    assert_equal error.expected.params, [Type::I32]
+    assert_nil error.provided
+  end
+
+  def test_link_error_incompatible_type
+    store = Store.new
+    module_ = Module.new store, '(module (import "env" "log" (func (param i32))))'
+
+    import_object = ImportObject.new
+    import_object.register "env", { :log => Memory.new(store, MemoryType.new(1, nil, false)) }
+
+    error = assert_raises(LinkError) {
+      Instance.new module_, import_object
+    }
+
+    assert_kind_of FunctionType, error.expected
+    assert_kind_of MemoryType, error.provided
+  end
+
+  def start_module
+    Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (global $counter (export "counter") (mut i32) (i32.const 0))
+        (func $start
+          global.get $counter
+          i32.const 1
+          i32.add
+          global.set $counter)
+        (start $start))
+      WAST
+    )
+  end
+
+  def test_start_function_is_called
+    assert_equal Instance.new(start_module, nil).exports.counter.value, 1
+  end
+
+  def test_start_function_is_deferred
+    instance = Instance.new start_module, nil, call_start: false
+    exports = instance.exports
+
+    assert_equal exports.counter.value, 0
+    assert_equal exports.keys, ["counter"]
+
+    instance.start
+    assert_equal exports.counter.value, 1
+
+    instance.start
+    assert_equal exports.counter.value, 1
+  end
+
+  def test_start_error
+    module_ = Module.new Store.new, "(module (func $start unreachable) (start $start))"
+
+    assert_raises(StartError) {
+      Instance.new module_, nil
+    }
+
+    instance = Instance.new module_, nil, call_start: false
+
+    assert_raises(StartError) {
+      instance.start
+    }
+  end
+
+  def test_start_function_of_deserialized_module_cannot_be_deferred
+    store = Store.new
+    module_ = Module.deserialize store, start_module.serialize
+
+    assert_raises(RuntimeError) {
+      Instance.new module_, nil, call_start: false
+    }
+  end
+
+  def test_start_function_is_deferred_twice
+    module_ = start_module
+
+    2.times do
+      instance = Instance.new module_, nil, call_start: false
+      assert_equal instance.exports.counter.value, 0
+
+      instance.start
+      assert_equal instance.exports.counter.value, 1
+    end
+  end
+
+  def test_start_function_cannot_be_deferred_with_a_conflicting_export
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (func $start)
+        (func (export "__wasmer_ruby_start"))
+        (start $start))
+      WAST
+    )
+
+    assert_equal Instance.new(module_, nil).exports.keys, ["__wasmer_ruby_start"]
+
+    assert_raises(RuntimeError) {
+      Instance.new module_, nil, call_start: false
+    }
+  end
+
+  def test_reactor_is_initialized
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (global $counter (export "counter") (mut i32) (i32.const 0))
+        (func (export "_initialize")
+          global.get $counter
+          i32.const 1
+          i32.add
+          global.set $counter))
+      WAST
+    )
+
+    instance = Instance.new module_, nil
+    assert_equal instance.exports.counter.value, 0
+    assert instance.exports.respond_to?(:_initialize)
+
+    instance = Instance.new module_, nil, initialize: true
+    assert_equal instance.exports.counter.value, 1
+    refute instance.exports.respond_to?(:_initialize)
+
+    instance = Instance.new module_, nil, call_start: false, initialize: true
+    assert_equal instance.exports.counter.value, 0
+
+    instance.start
+    assert_equal instance.exports.counter.value, 1
+  end
+
+  def hash_imports_module
+    Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (import "env" "log" (func $log (param i32)))
+        (import "env" "sum" (func $sum (param i32 i32) (result i32)))
+        (func (export "run") (param i32) (result i32)
+          local.get 0
+          call $log
+          local.get 0
+          i32.const 1
+          call $sum))
+      WAST
+    )
+  end
+
+  def test_hash_imports
+    logged = []
+    sum = lambda { |x, y| x + y }
+
+    instance = Instance.new(
+      hash_imports_module,
+      imports: {
+        "env" => {
+          "log" => proc { |x| logged << x },
+          :sum => sum.method(:call)
+        }
+      }
+    )
+
+    assert_equal instance.exports.run.(41), 42
+    assert_equal logged, [41]
+  end
+
+  def test_hash_imports_survive_gc
+    logged = []
+    instance = Instance.new(
+      hash_imports_module,
+      imports: { "env" => { "log" => proc { |x| logged << x }, "sum" => proc { |x, y| x + y } } }
+    )
+
+    GC.start
+
+    assert_equal instance.exports.run.(1), 2
+    assert_equal logged, [1]
+  end
+
+  def test_hash_imports_with_functions
+    store = Store.new
+    module_ = Module.new store, '(module (import "env" "log" (func (param i32))))'
+    function = Function.new store, proc { |x| }, FunctionType.new([Type::I32], [])
+
+    instance = Instance.new module_, imports: { env: { log: function } }
+
+    assert_kind_of Instance, instance
+  end
+
+  def test_hash_imports_unknown_function
+    assert_raises(TypeError) {
+      Instance.new hash_imports_module, imports: { "env" => { "unknown" => proc { } } }
+    }
+  end
+
+  def test_hash_imports_invalid_namespace
+    assert_raises(TypeError) {
+      Instance.new hash_imports_module, imports: { "env" => 42 }
+    }
+  end
+
+  def test_hash_imports_and_import_object
+    assert_raises(ArgumentError) {
+      Instance.new hash_imports_module, ImportObject.new, imports: {}
+    }
+  end
+
+  def test_exports_to_module
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (func (export "sum") (param i32 i32) (result i32)
+          local.get 0
+          local.get 1
+          i32.add)
+        (func (export "my-func"))
+        (func (export "my_func"))
+        (func (export "42") (result i64 f32)
+          i64.const 1
+          f32.const 2)
+        (memory (export "memory") 1))
+      WAST
+    )
+    exports = Instance.new(module_, nil).exports.to_module
+
+    assert_kind_of ::Module, exports
+    assert_equal exports.singleton_methods.sort, [:_42, :my_func, :my_func_, :sum, :inspect].sort
+    assert_equal exports.sum(1, 2), 3
+    assert_equal exports.method(:sum).arity, 2
+    assert_raises(ArgumentError) {
+      exports.sum(1)
+    }
+    assert_equal exports._42, [1, 2.0]
+    assert_equal exports.inspect, "#<Wasmer exports: sum(i32, i32) -> i32, my_func() -> (), my_func_() -> (), _42() -> (i64, f32)>"
+  end
+
+  def test_define_methods_on
+    object = Object.new
+    instance = Instance.new Module.new(Store.new, '(module (func (export "answer") (result i32) i32.const 42))'), nil
+
+    assert_same instance.define_methods_on(object), object
+    assert_equal object.answer, 42
+    assert_equal object.singleton_methods, [:answer]
+  end
+
+  def test_define_methods_on_skips_existing_methods
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (func (export "answer") (result i32) i32.const 42)
+        (func (export "send") (result i32) i32.const 1)
+        (func (export "class") (result i32) i32.const 2)
+        (func (export "inspect") (result i32) i32.const 3))
+      WAST
+    )
+    instance = Instance.new module_, nil
+    object = Object.new
+    instance.define_methods_on object
+
+    assert_equal object.singleton_methods, [:answer]
+    assert_equal object.class, Object
+
+    exports = instance.exports.to_module
+
+    assert_equal exports.singleton_methods.sort, [:answer, :inspect]
+    assert_equal exports.inspect, "#<Wasmer exports: answer() -> i32>"
+    assert_equal instance.exports.function("inspect").(), 3
+  end
 end
@@ -0,0 +1,80 @@
+require "prelude"
+
+class KeyringTest < Minitest::Test
+  def path(file_name)
+    File.expand_path(file_name, File.dirname(__FILE__))
+  end
+
+  def signed_bytes
+    IO.read path("signed.wasm"), mode: "rb"
+  end
+
+  def keyring
+    Keyring.new.add_key_file path("signed.pub")
+  end
+
+  def test_new
+    assert_equal Keyring.new.length, 0
+  end
+
+  def test_add_key
+    key = [IO.read(path("signed.pub")).strip].pack("H*")
+
+    assert_equal Keyring.new.add_key(key).length, 1
+  end
+
+  def test_add_invalid_key
+    assert_raises(ArgumentError) {
+      Keyring.new.add_key "foo"
+    }
+  end
+
+  def test_add_key_file
+    assert_equal keyring.add_key_file(path("untrusted.pub")).length, 2
+  end
+
+  def test_add_invalid_key_file
+    assert_raises(ArgumentError) {
+      Keyring.new.add_key_file path("prelude.rb")
+    }
+  end
+
+  def test_signature_section
+    assert_equal Keyring::SIGNATURE_SECTION, "signature"
+  end
+
+  def test_verify
+    module_ = Module.new Store.new, signed_bytes, verify: keyring
+
+    assert_equal module_.exports.length, Module.new(Store.new, signed_bytes).exports.length
+  end
+
+  def test_verify_missing_signature
+    bytes = IO.read path("tests.wasm"), mode: "rb"
+
+    error = assert_raises(SignatureError) {
+      Module.new Store.new, bytes, verify: keyring
+    }
+    assert_kind_of RuntimeError, error
+  end
+
+  def test_verify_untrusted_key
+    assert_raises(SignatureError) {
+      Module.new Store.new, signed_bytes, verify: Keyring.new.add_key_file(path("untrusted.pub"))
+    }
+  end
+
+  def test_verify_tampered_module
+    bytes = ModuleBytes.new(signed_bytes).add_custom_section("foo", "bar").to_s
+
+    assert_raises(SignatureError) {
+      Module.new Store.new, bytes, verify: keyring
+    }
+  end
+
+  def test_signed_payload_excludes_signature_section
+    unsigned_bytes = ModuleBytes.new(signed_bytes).strip_custom_section(Keyring::SIGNATURE_SECTION).to_s
+
+    assert_equal unsigned_bytes, IO.read(path("tests.wasm"), mode: "rb")
+  end
+end
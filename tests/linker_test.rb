@@ -0,0 +1,101 @@
+require "prelude"
+
+class LinkerTest < Minitest::Test
+  def libc(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (global $counter (mut i32) (i32.const 0))
+        (func (export "increment") (result i32)
+          global.get $counter
+          i32.const 1
+          i32.add
+          global.set $counter
+          global.get $counter))
+      WAST
+    )
+  end
+
+  def app(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "libc" "increment" (func $increment (result i32)))
+        (import "env" "offset" (global $offset i32))
+        (func (export "run") (result i32)
+          call $increment
+          global.get $offset
+          i32.add))
+      WAST
+    )
+  end
+
+  def linker(store)
+    linker = Linker.new
+    linker
+      .define("libc", libc(store))
+      .define(:one, app(store))
+      .define("two", app(store))
+      .register(:env, { :offset => Global.new(store, Value.i32(100), false) })
+  end
+
+  def test_instantiate
+    linker = linker Store.new
+
+    assert_equal linker.modules, ["libc", "one", "two"]
+    assert_nil linker["libc"]
+
+    one = linker.instantiate "one"
+    two = linker.instantiate :two
+
+    assert_kind_of Instance, one
+    assert_kind_of Instance, linker["libc"]
+    assert_same linker.instantiate("one"), one
+
+    # `libc` is shared by both instances.
+    assert_equal one.exports.run.(), 101
+    assert_equal two.exports.run.(), 102
+    assert_equal linker["libc"].exports.increment.(), 3
+  end
+
+  def test_undefined_module
+    assert_raises(ArgumentError) {
+      Linker.new.instantiate "foo"
+    }
+  end
+
+  def test_already_defined_module
+    store = Store.new
+    linker = Linker.new.define "libc", libc(store)
+
+    assert_raises(ArgumentError) {
+      linker.define "libc", libc(store)
+    }
+  end
+
+  def test_missing_import
+    store = Store.new
+    linker = Linker.new.define("libc", libc(store)).define("app", app(store))
+
+    error = assert_raises(LinkError) {
+      linker.instantiate "app"
+    }
+
+    assert_equal error.module, "env"
+  end
+
+  def test_cycle
+    store = Store.new
+    linker = Linker.new
+    linker.define "a", Module.new(store, '(module (import "b" "f" (func)) (func (export "f")))')
+    linker.define "b", Module.new(store, '(module (import "a" "f" (func)) (func (export "f")))')
+
+    error = assert_raises(InstantiationError) {
+      linker.instantiate "a"
+    }
+
+    assert_match "a -> b -> a", error.message
+  end
+end
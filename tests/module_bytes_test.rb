@@ -0,0 +1,71 @@
+require "prelude"
+
+class ModuleBytesTest < Minitest::Test
+  def bytes
+    IO.read File.expand_path("custom_sections.wasm", File.dirname(__FILE__)), mode: "rb"
+  end
+
+  def test_new
+    assert ModuleBytes.new bytes
+  end
+
+  def test_new_from_wat
+    assert_equal ModuleBytes.new("(module)").to_s, "\x00asm\x01\x00\x00\x00".b
+  end
+
+  def test_new_invalid
+    assert_raises(RuntimeError) {
+      ModuleBytes.new "foo"
+    }
+  end
+
+  def test_custom_sections
+    module_bytes = ModuleBytes.new bytes
+
+    assert_includes module_bytes.custom_section_names, "easter_egg"
+    assert_equal module_bytes.custom_sections("easter_egg"), ["Wasmer"]
+    assert_equal module_bytes.custom_sections("foo"), []
+  end
+
+  def test_unchanged
+    assert_equal ModuleBytes.new(bytes).to_s, bytes
+  end
+
+  def test_add_custom_section
+    bytes = ModuleBytes.new("(module)").add_custom_section("foo", "bar").to_s
+    module_ = Module.new Store.new, bytes
+
+    assert_equal module_.custom_sections("foo"), ["bar"]
+  end
+
+  def test_replace_custom_section
+    module_bytes = ModuleBytes.new bytes
+    module_bytes.add_custom_section "easter_egg", "Ruby"
+    module_bytes.replace_custom_section "easter_egg", "WebAssembly"
+    module_bytes.replace_custom_section "foo", "bar"
+
+    module_ = Module.new Store.new, module_bytes.to_s
+
+    assert_equal module_.custom_sections("easter_egg"), ["WebAssembly"]
+    assert_equal module_.custom_sections("hello"), ["World!"]
+    assert_equal module_.custom_sections("foo"), ["bar"]
+  end
+
+  def test_strip_custom_section
+    module_bytes = ModuleBytes.new(bytes).strip_custom_section("easter_egg")
+
+    assert_equal module_bytes.custom_sections("easter_egg"), []
+    assert_equal module_bytes.custom_sections("hello"), ["World!"]
+  end
+
+  def test_strip_debug_sections
+    module_bytes = ModuleBytes.new("(module (func $foo))")
+
+    assert_equal module_bytes.custom_section_names, ["name"]
+
+    module_ = Module.new Store.new, module_bytes.strip_debug_sections.to_s
+
+    assert_equal module_.custom_section_names, []
+    assert_nil module_.functions[0].name
+  end
+end
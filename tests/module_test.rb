@@ -17,6 +17,26 @@ class ModuleTest < Minitest::Test
     assert not(Module.validate Store.new, self.invalid_bytes)
   end
 
+  def test_validate_with_features
+    bytes = Wasmer::wat2wasm(
+      (<<~WAST)
+      (module
+        (func (result i32 i32)
+          i32.const 1
+          i32.const 2))
+      WAST
+    )
+
+    assert Module.validate Store.new, bytes
+    assert not(Module.validate Store.new(features: Features.new(multi_value: false)), bytes)
+  end
+
+  def test_compile_with_features
+    assert_raises(RuntimeError) {
+      Module.new Store.new(features: Features.new(simd: false)), "(module (func (result v128) v128.const i64x2 0 0))"
+    }
+  end
+
   def test_compile_bytes
     assert Module.new Store.new, self.bytes
   end
@@ -136,6 +156,74 @@ class ModuleTest < Minitest::Test
     assert_equal module_.custom_sections("foo"), []
   end
 
+  def test_custom_section_names
+    bytes = IO.read File.expand_path("custom_sections.wasm", File.dirname(__FILE__)), mode: "rb"
+    module_ = Module.new Store.new, bytes
+
+    assert_includes module_.custom_section_names, "easter_egg"
+    assert_includes module_.custom_section_names, "hello"
+    assert_equal Module.new(Store.new, "(module)").custom_section_names, []
+  end
+
+  def test_functions
+    functions = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (import "env" "log" (func $log (param i32)))
+        (func $sum (param i32 i32) (result i32)
+          local.get 0
+          local.get 1
+          i32.add)
+        (func (result i64)
+          i64.const 42))
+      WAST
+    ).functions
+
+    assert_equal functions.length, 2
+
+    assert_kind_of FunctionInfo, functions[0]
+    assert_equal functions[0].index, 1
+    assert_equal functions[0].name, "sum"
+    assert_equal functions[0].type.params, [Type::I32, Type::I32]
+    assert_equal functions[0].type.results, [Type::I32]
+
+    assert_equal functions[1].index, 2
+    assert_nil functions[1].name
+    assert_equal functions[1].type.params, []
+    assert_equal functions[1].type.results, [Type::I64]
+  end
+
+  def test_start_function
+    assert_equal Module.new(Store.new, "(module (func) (func) (start 1))").start_function, 1
+    assert_nil Module.new(Store.new, "(module)").start_function
+  end
+
+  def test_memories_globals_tables
+    module_ = Module.new(
+      Store.new,
+      (<<~WAST)
+      (module
+        (import "env" "memory" (memory 1))
+        (import "env" "global" (global i32))
+        (table 2 funcref)
+        (global (mut i64) (i64.const 0)))
+      WAST
+    )
+
+    assert_equal module_.memories, []
+
+    globals = module_.globals
+    assert_equal globals.length, 1
+    assert_equal globals[0].type, Type::I64
+    assert_equal globals[0].mutable?, true
+
+    tables = module_.tables
+    assert_equal tables.length, 1
+    assert_equal tables[0].type, Type::FUNC_REF
+    assert_equal tables[0].minimum, 2
+  end
+
   def test_serialize
     module_ = Module.new Store.new, "(module)"
     assert_kind_of String, module_.serialize
@@ -163,4 +251,58 @@ class ModuleTest < Minitest::Test
     assert_equal exports[0].type.params, [Type::I32, Type::I64]
     assert_equal exports[0].type.results, []
   end
+
+  def test_deserialize_invalid_bytes
+    assert_raises(RuntimeError) {
+      Module.deserialize Store.new, "foo"
+    }
+  end
+
+  def test_deserialize_for_another_target
+    triple =
+      if Target.host.triple.start_with? "x86_64"
+        "aarch64-unknown-linux-gnu"
+      else
+        "x86_64-unknown-linux-gnu"
+      end
+
+    serialized_module = Module.new(
+      Store.new(target: Target.new(triple, [])),
+      "(module)"
+    ).serialize
+
+    error = assert_raises(RuntimeError) {
+      Module.deserialize Store.new, serialized_module
+    }
+    assert_match triple, error.message
+  end
+
+  def test_deserialize_for_missing_cpu_features
+    host = Target.host
+    missing_cpu_features = ["sse3", "ssse3", "sse4.1", "sse4.2", "popcnt", "avx", "bmi1", "bmi2", "avx2", "lzcnt"] - host.cpu_features
+
+    skip "The host has all the CPU features" if !host.triple.start_with?("x86_64") || missing_cpu_features.empty?
+
+    serialized_module = Module.new(
+      Store.new(target: Target.new(host.triple, host.cpu_features + [missing_cpu_features.first])),
+      "(module)"
+    ).serialize
+
+    error = assert_raises(RuntimeError) {
+      Module.deserialize Store.new, serialized_module
+    }
+    assert_match missing_cpu_features.first, error.message
+  end
+
+  def test_deserialize_without_target
+    store = Store.new
+    serialized_module = Module.new(store, "(module)").serialize
+
+    # Strip the prefix, the triple and the CPU features, like a module
+    # serialized by a previous version.
+    offset = "\0wasmer-ruby-module\0".bytesize
+    2.times { offset += 4 + serialized_module.byteslice(offset, 4).unpack1("V") }
+
+    assert_kind_of Module, Module.deserialize(store, serialized_module.byteslice(offset..))
+  end
 end
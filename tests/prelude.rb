@@ -7,24 +7,35 @@ require "minitest/reporters"
 
 Minitest::Reporters.use! Minitest::Reporters::SpecReporter.new
 
+Emscripten = Wasmer::Emscripten
 ExportType = Wasmer::ExportType
 Exports = Wasmer::Exports
+Features = Wasmer::Features
 Function = Wasmer::Function
+FunctionInfo = Wasmer::FunctionInfo
 FunctionType = Wasmer::FunctionType
 Global = Wasmer::Global
 GlobalType = Wasmer::GlobalType
 ImportObject = Wasmer::ImportObject
 ImportType = Wasmer::ImportType
 Instance = Wasmer::Instance
+InstantiationError = Wasmer::InstantiationError
 Int16Array = Wasmer::Int16Array
 Int32Array = Wasmer::Int32Array
 Int8Array = Wasmer::Int8Array
+Keyring = Wasmer::Keyring
+LinkError = Wasmer::LinkError
+Linker = Wasmer::Linker
 Memory = Wasmer::Memory
 MemoryType = Wasmer::MemoryType
 Module = Wasmer::Module
+ModuleBytes = Wasmer::ModuleBytes
+SignatureError = Wasmer::SignatureError
+StartError = Wasmer::StartError
 Store = Wasmer::Store
 Table = Wasmer::Table
 TableType = Wasmer::TableType
+Target = Wasmer::Target
 Type = Wasmer::Type
 Uint16Array = Wasmer::Uint16Array
 Uint32Array = Wasmer::Uint32Array
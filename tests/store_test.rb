@@ -4,4 +4,66 @@ class StoreTest < Minitest::Test
   def test_new
     assert Store.new
   end
+
+  def test_new_with_features
+    assert Store.new features: Features.new
+  end
+
+  def test_default_features
+    features = Store.new.features
+
+    assert_kind_of Features, features
+    assert_equal features.simd?, true
+    assert_equal features.threads?, false
+    assert_equal features.reference_types?, true
+    assert_equal features.bulk_memory?, true
+    assert_equal features.multi_value?, true
+    assert_equal features.memory64?, false
+    assert_equal features.module_linking?, false
+  end
+
+  def test_features
+    features = Store.new(features: Features.new(simd: false, threads: true)).features
+
+    assert_equal features.simd?, false
+    assert_equal features.threads?, true
+    assert_equal features.multi_value?, true
+  end
+
+  def test_features_bulk_memory_disables_reference_types
+    features = Features.new bulk_memory: false
+
+    assert_equal features.bulk_memory?, false
+    assert_equal features.reference_types?, false
+  end
+
+  def test_features_unknown_keyword
+    assert_raises(ArgumentError) {
+      Features.new foo: true
+    }
+  end
+
+  def test_default_target
+    assert_equal Store.new.target.triple, Target.host.triple
+  end
+
+  def test_target
+    target = Target.new "x86_64-unknown-linux-gnu", ["sse2", "avx"]
+
+    assert_equal target.triple, "x86_64-unknown-linux-gnu"
+    assert_equal target.cpu_features.sort, ["avx", "sse2"]
+    assert_equal Store.new(target: target).target.triple, "x86_64-unknown-linux-gnu"
+  end
+
+  def test_target_invalid_triple
+    assert_raises(ArgumentError) {
+      Target.new "foo-bar-baz-qux", []
+    }
+  end
+
+  def test_target_invalid_cpu_feature
+    assert_raises(ArgumentError) {
+      Target.new "x86_64-unknown-linux-gnu", ["foo"]
+    }
+  end
 end
@@ -5,6 +5,70 @@ class WasiTest < Minitest::Test
     IO.read File.expand_path("wasi.wasm", File.dirname(__FILE__)), mode: "rb"
   end
 
+  # A `cat` program, copying its standard input to its standard output.
+  def cat_module(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+          (local $length i32)
+          (block $done
+            (loop $loop
+              (i32.store (i32.const 0) (i32.const 16))
+              (i32.store (i32.const 4) (i32.const 64))
+              (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+              (local.set $length (i32.load (i32.const 8)))
+              (br_if $done (i32.eqz (local.get $length)))
+              (i32.store (i32.const 4) (local.get $length))
+              (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+              (br $loop)))))
+      WAST
+    )
+  end
+
+  # A `cp` program, copying the `input.txt` file to `output.txt`, in
+  # the first preopened directory (fd 4, after the virtual root).
+  def cp_module(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 100) "input.txt")
+        (data (i32.const 120) "output.txt")
+        (func (export "_start")
+          (drop (call $path_open (i32.const 4) (i32.const 0) (i32.const 100) (i32.const 9) (i32.const 0) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 200)))
+          (drop (call $path_open (i32.const 4) (i32.const 0) (i32.const 120) (i32.const 10) (i32.const 9) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 204)))
+          (i32.store (i32.const 0) (i32.const 16))
+          (i32.store (i32.const 4) (i32.const 64))
+          (drop (call $fd_read (i32.load (i32.const 200)) (i32.const 0) (i32.const 1) (i32.const 8)))
+          (i32.store (i32.const 4) (i32.load (i32.const 8)))
+          (drop (call $fd_write (i32.load (i32.const 204)) (i32.const 0) (i32.const 1) (i32.const 8)))))
+      WAST
+    )
+  end
+
+  def run_cat(stdin)
+    store = Store.new
+    module_ = cat_module store
+    wasi_env = Wasi::StateBuilder.new("cat")
+                 .stdin(stdin)
+                 .capture_stdout
+                 .finalize
+    import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, true)
+
+    Instance.new(module_, import_object).exports._start.()
+
+    wasi_env.stdout
+  end
+
   def test_version
     assert_equal Wasi::Version::LATEST, 1
     assert_equal Wasi::Version::SNAPSHOT0, 2
@@ -15,6 +79,7 @@ class WasiTest < Minitest::Test
     module_ = Module.new(Store.new, bytes)
 
     assert_equal Wasi::get_version(module_, true), Wasi::Version::SNAPSHOT1
+    assert_equal Wasi::get_version(module_, true, as: :symbol), :snapshot1
   end
 
   def test_state_builder
@@ -40,6 +105,63 @@ class WasiTest < Minitest::Test
     assert_kind_of Instance, instance
   end
 
+  def test_generate_import_object_with_symbol
+    store = Store.new
+    wasi_env = Wasi::StateBuilder.new("foo").finalize
+
+    assert wasi_env.generate_import_object(store, :snapshot0).contains_namespace?("wasi_unstable")
+    assert wasi_env.generate_import_object(store, :snapshot1).contains_namespace?("wasi_snapshot_preview1")
+
+    module_ = Module.new store, bytes
+    version = Wasi::get_version module_, true, as: :symbol
+    assert wasi_env.generate_import_object(store, version).contains_namespace?("wasi_snapshot_preview1")
+
+    assert_raises(TypeError) {
+      wasi_env.generate_import_object store, :snapshot2
+    }
+  end
+
+  def test_generate_import_object_with_module
+    store = Store.new
+    module_ = Module.new store, bytes
+    wasi_env = Wasi::StateBuilder.new("test-program").finalize
+    import_object = wasi_env.generate_import_object store, module_
+
+    assert import_object.contains_namespace?("wasi_snapshot_preview1")
+    assert_kind_of Instance, Instance.new(module_, import_object)
+
+    error = assert_raises(ArgumentError) {
+      wasi_env.generate_import_object store, Module.new(store, "(module)")
+    }
+    assert_equal error.message, "The WASI version cannot be detected, because the module does not import any WASI namespace"
+  end
+
+  def test_generate_import_object_with_extra
+    store = Store.new
+    log = Function.new store, proc { }, FunctionType.new([Type::I32], [])
+    random_get = Function.new store, proc { 0 }, FunctionType.new([Type::I32, Type::I32], [Type::I32])
+    wasi_env = Wasi::StateBuilder.new("foo").finalize
+
+    import_object = wasi_env.generate_import_object(
+      store,
+      Wasi::Version::SNAPSHOT1,
+      extra: { "env" => { "log" => log }, "wasi_snapshot_preview1" => { :my_log => log } }
+    )
+
+    assert_equal import_object.get("env", "log"), log
+    assert_equal import_object.get("wasi_snapshot_preview1", "my_log"), log
+    assert_kind_of Function, import_object.get("wasi_snapshot_preview1", "fd_write")
+
+    error = assert_raises(NameError) {
+      wasi_env.generate_import_object(
+        store,
+        Wasi::Version::SNAPSHOT1,
+        extra: { "wasi_snapshot_preview1" => { "random_get" => random_get } }
+      )
+    }
+    assert_equal error.message, "The import `random_get` is already registered in the `wasi_snapshot_preview1` namespace"
+  end
+
   def test_wasi
     store = Store.new
     module_ = Module.new store, bytes
@@ -54,4 +176,470 @@ class WasiTest < Minitest::Test
 
     instance.exports._start.()
   end
+
+  def test_capture_stdout
+    store = Store.new
+    module_ = Module.new store, bytes
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .argument("--foo")
+                 .capture_stdout
+                 .capture_stderr
+                 .finalize
+    import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, true)
+    instance = Instance.new module_, import_object
+
+    instance.exports._start.()
+
+    stdout = wasi_env.stdout
+
+    assert_match "Found program name: `test-program`", stdout
+    assert_match "Found 1 arguments: --foo", stdout
+    assert_equal wasi_env.stdout, ""
+    assert_equal wasi_env.stderr, ""
+  end
+
+  def test_stdout_not_captured
+    assert_raises(RuntimeError) {
+      Wasi::StateBuilder.new("test-program").finalize.stdout
+    }
+  end
+
+  def test_stdin_string
+    assert_equal run_cat("Hello, World!"), "Hello, World!"
+  end
+
+  def test_stdin_io
+    require "stringio"
+
+    input = "abcdefghij" * 20
+    io = StringIO.new input
+
+    assert_equal run_cat(io), input
+    assert io.eof?
+  end
+
+  def test_stdin_io_raises
+    io = Object.new
+
+    def io.read(length)
+      raise IOError, "oops"
+    end
+
+    # The guest gets an errno, and reads nothing.
+    assert_equal run_cat(io), ""
+  end
+
+  def test_finalize_twice
+    store = Store.new
+    module_ = cat_module store
+    state_builder = Wasi::StateBuilder.new("cat")
+                      .stdin("Hello, World!")
+                      .capture_stdout
+
+    2.times do
+      wasi_env = state_builder.finalize
+      import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, true)
+
+      Instance.new(module_, import_object).exports._start.()
+
+      assert_equal wasi_env.stdout, "Hello, World!"
+    end
+  end
+
+  def test_stdin_invalid
+    assert_raises(TypeError) {
+      Wasi::StateBuilder.new("cat").stdin(42)
+    }
+  end
+
+  def test_memfs
+    memfs = Wasi::MemFS.new
+    memfs.mkdir "/foo"
+    memfs.write_file "/foo/bar.txt", "baz"
+    memfs.write_file "qux.txt", "quux"
+
+    assert_equal memfs.read_file("/foo/bar.txt"), "baz"
+    assert_equal memfs.list, ["foo", "qux.txt"]
+    assert_equal memfs.list("/foo"), ["bar.txt"]
+  end
+
+  def test_memfs_missing_file
+    assert_raises(RuntimeError) {
+      Wasi::MemFS.new.read_file "/missing.txt"
+    }
+  end
+
+  def test_mount
+    store = Store.new
+    module_ = cp_module store
+    memfs = Wasi::MemFS.new
+    memfs.write_file "/input.txt", "Hello, World!"
+
+    wasi_env = Wasi::StateBuilder.new("cp")
+                 .mount("/data", memfs)
+                 .finalize
+    import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, true)
+
+    Instance.new(module_, import_object).exports._start.()
+
+    assert_equal memfs.list, ["input.txt", "output.txt"]
+    assert_equal memfs.read_file("/output.txt"), "Hello, World!"
+  end
+
+  def test_mount_relative_path
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("cp").mount("data", Wasi::MemFS.new)
+    }
+  end
+
+  def run_cp(&configure)
+    store = Store.new
+    module_ = cp_module store
+    wasi_env = configure.(Wasi::StateBuilder.new("cp")).finalize
+    import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, true)
+
+    Instance.new(module_, import_object).exports._start.()
+  end
+
+  def test_preopen_directory_read_only
+    require "tmpdir"
+
+    Dir.mktmpdir do |directory|
+      IO.write File.join(directory, "input.txt"), "Hello, World!"
+      IO.write File.join(directory, "output.txt"), "untouched"
+
+      run_cp { |state_builder| state_builder.preopen_directory(directory, write: false) }
+
+      assert_equal IO.read(File.join(directory, "output.txt")), "untouched"
+
+      run_cp { |state_builder| state_builder.preopen_directory(directory) }
+
+      assert_equal IO.read(File.join(directory, "output.txt")), "Hello, World!"
+    end
+  end
+
+  def test_map_directory_read_only
+    require "tmpdir"
+
+    Dir.mktmpdir do |directory|
+      IO.write File.join(directory, "input.txt"), "Hello, World!"
+      IO.write File.join(directory, "output.txt"), "untouched"
+
+      run_cp { |state_builder| state_builder.map_directory("data", directory, read: true, write: false) }
+
+      assert_equal IO.read(File.join(directory, "output.txt")), "untouched"
+    end
+  end
+
+  def test_preopen_directory_invalid_permissions
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("cp").preopen_directory(".", write: false, create: true)
+    }
+
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("cp").preopen_directory(".", execute: true)
+    }
+  end
+
+  def exit_module(store, body)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+          #{body}))
+      WAST
+    )
+  end
+
+  def test_run
+    store = Store.new
+    module_ = Module.new store, bytes
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .capture_stdout
+                 .finalize
+
+    assert_equal wasi_env.run(module_), 0
+    assert_match "Found program name: `test-program`", wasi_env.stdout
+  end
+
+  def test_run_exit_code
+    store = Store.new
+    wasi_env = Wasi::StateBuilder.new("test-program").finalize
+
+    assert_equal wasi_env.run(exit_module(store, "(call $proc_exit (i32.const 42))")), 42
+    assert_equal wasi_env.run(exit_module(store, "(call $proc_exit (i32.const 0))")), 0
+  end
+
+  def test_run_trap
+    store = Store.new
+    wasi_env = Wasi::StateBuilder.new("test-program").finalize
+
+    assert_raises(RuntimeError) {
+      wasi_env.run exit_module(store, "(unreachable)")
+    }
+  end
+
+  def test_run_not_wasi
+    store = Store.new
+    module_ = Module.new store, "(module)"
+
+    assert_raises(TypeError) {
+      Wasi::StateBuilder.new("test-program").finalize.run module_
+    }
+  end
+
+  def test_run_twice
+    store = Store.new
+    module_ = Module.new store, bytes
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .argument("--foo")
+                 .capture_stdout
+                 .finalize
+
+    assert_equal wasi_env.run(module_), 0
+    assert_match "Found 1 arguments: --foo", wasi_env.stdout
+
+    wasi_env.arguments = ["--bar", "--baz"]
+
+    assert_equal wasi_env.run(module_), 0
+    assert_match "Found 2 arguments: --bar, --baz", wasi_env.stdout
+  end
+
+  def test_arguments
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .arguments(["--foo", "--bar"])
+                 .finalize
+
+    assert_equal wasi_env.arguments, ["--foo", "--bar"]
+
+    wasi_env.arguments = ["--baz"]
+
+    assert_equal wasi_env.arguments, ["--baz"]
+  end
+
+  def test_environment_variables
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .environments({"ABC" => "DEF", "X" => "Y=Z"})
+                 .finalize
+
+    assert_equal wasi_env.environment_variables, {"ABC" => "DEF", "X" => "Y=Z"}
+
+    wasi_env.environment_variables = {"FOO" => "BAR"}
+
+    assert_equal wasi_env.environment_variables, {"FOO" => "BAR"}
+
+    assert_raises(ArgumentError) {
+      wasi_env.environment_variables = {"A=B" => "C"}
+    }
+  end
+
+  def test_file_descriptors
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .preopen_directory(".")
+                 .finalize
+
+    assert_equal wasi_env.file_descriptors, {0 => "stdin", 1 => "stdout", 2 => "stderr", 3 => "/", 4 => "."}
+  end
+
+  def test_read_file
+    require "tmpdir"
+
+    Dir.mktmpdir do |directory|
+      IO.write File.join(directory, "input.txt"), "Hello, World!"
+
+      wasi_env = Wasi::StateBuilder.new("cp")
+                   .map_directory("data", directory)
+                   .finalize
+
+      assert_equal wasi_env.preopened_directories, {"data" => directory}
+      assert_equal wasi_env.run(cp_module(Store.new)), 0
+      assert_equal wasi_env.read_file("data/output.txt"), "Hello, World!"
+      assert_equal wasi_env.read_file("/data/output.txt"), "Hello, World!"
+
+      assert_raises(NameError) {
+        wasi_env.read_file "elsewhere/output.txt"
+      }
+
+      assert_raises(RuntimeError) {
+        wasi_env.read_file "data/missing.txt"
+      }
+    end
+  end
+
+  def test_read_file_of_mount
+    memfs = Wasi::MemFS.new
+    memfs.write_file "/input.txt", "Hello, World!"
+    wasi_env = Wasi::StateBuilder.new("cp")
+                 .mount("/data", memfs)
+                 .finalize
+
+    assert_equal wasi_env.run(cp_module(Store.new)), 0
+    assert_equal wasi_env.read_file("/data/output.txt"), "Hello, World!"
+  end
+
+  # Writes the time of the realtime clock, and 16 random bytes, to the
+  # standard output.
+  def time_and_random_module(store)
+    Module.new(
+      store,
+      (<<~WAST)
+      (module
+        (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+          (drop (call $clock_time_get (i32.const 0) (i64.const 0) (i32.const 16)))
+          (drop (call $random_get (i32.const 24) (i32.const 16)))
+          (i32.store (i32.const 0) (i32.const 16))
+          (i32.store (i32.const 4) (i32.const 24))
+          (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+      WAST
+    )
+  end
+
+  def run_time_and_random(&configure)
+    wasi_env = configure.(Wasi::StateBuilder.new("test-program").capture_stdout).finalize
+    wasi_env.run time_and_random_module(Store.new)
+
+    output = wasi_env.stdout.b
+
+    [output[0, 8].unpack1("Q<"), output[8, 16]]
+  end
+
+  def test_clock_fixed
+    time, _ = run_time_and_random { |state_builder| state_builder.clock(:fixed, 42) }
+    assert_equal time, 42
+
+    time, _ = run_time_and_random { |state_builder| state_builder.clock(:fixed, Time.at(1, 500, :nsec)) }
+    assert_equal time, 1_000_000_500
+  end
+
+  def test_clock_block
+    clocks = []
+    time, _ = run_time_and_random { |state_builder|
+      state_builder.clock { |clock|
+        clocks << clock
+        7
+      }
+    }
+
+    assert_equal time, 7
+    assert_equal clocks, [:realtime]
+  end
+
+  def test_clock_block_raises
+    error = assert_raises(RuntimeError) {
+      run_time_and_random { |state_builder| state_builder.clock { |_| raise "oops" } }
+    }
+
+    assert_match(/oops/, error.message)
+  end
+
+  def test_clock_invalid
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("test-program").clock(:fixed)
+    }
+
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("test-program").clock(:fixed, Time.at(2 ** 62))
+    }
+
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("test-program").clock(:fixed, Time.at(-1))
+    }
+  end
+
+  def test_random_seed
+    _, first = run_time_and_random { |state_builder| state_builder.random_seed(42) }
+    _, second = run_time_and_random { |state_builder| state_builder.random_seed(42) }
+    _, third = run_time_and_random { |state_builder| state_builder.random_seed(7) }
+
+    assert_equal first.bytesize, 16
+    assert_equal first, second
+    refute_equal first, third
+  end
+
+  def test_trace
+    memfs = Wasi::MemFS.new
+    memfs.write_file "/input.txt", "Hello, World!"
+    calls = []
+
+    run_cp { |state_builder|
+      state_builder
+        .mount("/data", memfs)
+        .trace { |syscall, arguments, errno| calls << [syscall, arguments, errno] }
+    }
+
+    assert_equal calls.map(&:first), [:path_open, :path_open, :fd_read, :fd_write]
+    assert_equal(
+      calls[0],
+      [
+        :path_open,
+        {
+          fd: 4,
+          dirflags: 0,
+          path: "input.txt",
+          oflags: 0,
+          fs_rights_base: -1,
+          fs_rights_inheriting: -1,
+          fdflags: 0,
+          opened_fd: 5
+        },
+        0
+      ]
+    )
+    assert_equal calls[2], [:fd_read, { fd: 5, iovs: 64, nread: 13 }, 0]
+    assert_equal calls[3], [:fd_write, { fd: 6, iovs: 13, nwritten: 13 }, 0]
+  end
+
+  def test_trace_io
+    require "stringio"
+
+    io = StringIO.new
+    run_time_and_random { |state_builder| state_builder.clock(:fixed, 42).trace(io) }
+
+    assert_includes io.string, "clock_time_get(clock_id: 0, precision: 0, time: 42) = 0\n"
+    assert_includes io.string, "fd_write(fd: 1, iovs: 16, nwritten: 16) = 0\n"
+
+    io = StringIO.new
+    run_cp { |state_builder| state_builder.mount("/data", Wasi::MemFS.new).trace(io) }
+
+    assert_match(/^path_open\(fd: 4, .*path: "input.txt", .*opened_fd: \?\) = \d+ \(E[A-Z]+\)$/, io.string)
+  end
+
+  def test_trace_several_instances
+    store = Store.new
+    module_ = time_and_random_module store
+    calls = []
+    wasi_env = Wasi::StateBuilder.new("test-program")
+                 .capture_stdout
+                 .clock(:fixed, 42)
+                 .trace { |syscall, arguments, errno| calls << [syscall, arguments[:time], errno] }
+                 .finalize
+    import_object = wasi_env.generate_import_object store, Wasi::get_version(module_, false)
+
+    2.times do
+      Instance.new(module_, import_object).exports._start.()
+    end
+
+    assert_equal calls.select { |call| call[0] == :clock_time_get }, [[:clock_time_get, 42, 0]] * 2
+  end
+
+  def test_trace_raises
+    error = assert_raises(RuntimeError) {
+      run_time_and_random { |state_builder| state_builder.trace { |_, _, _| raise "oops" } }
+    }
+
+    assert_match(/oops/, error.message)
+  end
+
+  def test_trace_invalid
+    assert_raises(ArgumentError) {
+      Wasi::StateBuilder.new("test-program").trace
+    }
+  end
 end